@@ -20,6 +20,20 @@ local function startup()
   components:insert(ent, DemoSprite:create())
 end
 
+local function spawn_with_commands()
+  local meta = assets.root
+  local data = assets:get(meta.data)
+
+  -- Commands are applied at the end of the stage
+  local ent = commands:spawn()
+  local transform = Transform:create()
+  transform.translation.x = 100
+  commands:insert(ent, transform)
+  local sprite = Sprite:create()
+  sprite.image = data.sprite
+  commands:insert(ent, sprite)
+end
+
 local function update()
   local entities = resources:get(Entities)
   local time = resources:get(Time)
@@ -30,5 +44,12 @@ local function update()
   end
 end
 
+local function on_demo_sprite_inserted(ent)
+  local transform = components:get(ent, Transform)
+  info("Demo sprite added at", transform.translation.x)
+end
+
 session:add_startup_system(startup)
+session:add_startup_system(spawn_with_commands)
 session:add_system_to_stage(CoreStage.Update, update)
+session:on_insert(DemoSprite, on_demo_sprite_inserted)
@@ -193,12 +193,16 @@ pub fn sync_cameras(
 
         let entities = world.resource::<bones::Entities>();
         let transforms = world.components.get::<bones::Transform>().borrow();
+        let global_transforms = world.components.get::<bones::GlobalTransform>().borrow();
         let cameras = world.components.get::<bones::Camera>().borrow();
 
         // Sync cameras
-        for (_ent, (transform, camera)) in entities.iter_with((&transforms, &cameras)) {
+        for (ent, (transform, camera)) in entities.iter_with((&transforms, &cameras)) {
             // Add each camera to the bevy world
-            add_bones_camera(camera, transform)
+            add_bones_camera(
+                camera,
+                &render_transform(&global_transforms, ent, transform),
+            )
         }
     }
 
@@ -257,12 +261,14 @@ pub fn extract_bones_sprites(
 
         let entities = world.resource::<bones::Entities>();
         let transforms = world.components.get::<bones::Transform>().borrow();
+        let global_transforms = world.components.get::<bones::GlobalTransform>().borrow();
         let sprites = world.components.get::<bones::Sprite>().borrow();
         let atlas_sprites = world.components.get::<bones::AtlasSprite>().borrow();
 
         // Extract normal sprites
         let mut z_offset = 0.0;
-        for (_, (sprite, transform)) in entities.iter_with((&sprites, &transforms)) {
+        for (ent, (sprite, transform)) in entities.iter_with((&sprites, &transforms)) {
+            let transform = render_transform(&global_transforms, ent, transform);
             let sprite_image = match bones_assets.try_get(sprite.image) {
                 Some(Ok(image)) => image,
                 Some(Err(err)) => {
@@ -303,7 +309,8 @@ pub fn extract_bones_sprites(
         }
 
         // Extract atlas sprites
-        for (_, (atlas_sprite, transform)) in entities.iter_with((&atlas_sprites, &transforms)) {
+        for (ent, (atlas_sprite, transform)) in entities.iter_with((&atlas_sprites, &transforms)) {
+            let transform = render_transform(&global_transforms, ent, transform);
             let atlas = bones_assets.get(atlas_sprite.atlas);
             let atlas_image = bones_assets.get(atlas.image);
             let image_id = if let bones::Image::External(id) = &*atlas_image {
@@ -383,11 +390,13 @@ pub fn extract_bones_tilemaps(
 
         let entities = world.resource::<bones::Entities>();
         let transforms = world.components.get::<bones::Transform>().borrow();
+        let global_transforms = world.components.get::<bones::GlobalTransform>().borrow();
         let tile_layers = world.components.get::<bones::TileLayer>().borrow();
         let tiles = world.components.get::<bones::Tile>().borrow();
 
         // Extract tiles as sprites
-        for (_, (tile_layer, transform)) in entities.iter_with((&tile_layers, &transforms)) {
+        for (ent, (tile_layer, transform)) in entities.iter_with((&tile_layers, &transforms)) {
+            let transform = render_transform(&global_transforms, ent, transform);
             let atlas = bones_assets.get(tile_layer.atlas);
             let atlas_image = bones_assets.get(atlas.image);
             let image_id = if let bones::Image::External(id) = &*atlas_image {
@@ -541,11 +550,15 @@ pub fn sync_bones_path2ds(
 
         let entities = world.resource::<bones::Entities>();
         let transforms = world.components.get::<bones::Transform>().borrow();
+        let global_transforms = world.components.get::<bones::GlobalTransform>().borrow();
         let path2ds = world.components.get::<bones::Path2d>().borrow();
 
         // Extract tiles as sprites
-        for (_, (path2d, transform)) in entities.iter_with((&path2ds, &transforms)) {
-            add_bones_path2d(path2d, transform);
+        for (ent, (path2d, transform)) in entities.iter_with((&path2ds, &transforms)) {
+            add_bones_path2d(
+                path2d,
+                &render_transform(&global_transforms, ent, transform),
+            );
         }
     }
 
@@ -554,3 +567,15 @@ pub fn sync_bones_path2ds(
         commands.entity(ent).despawn()
     }
 }
+
+/// Get the transform to render an entity at: its [`bones::GlobalTransform`], or its
+/// [`bones::Transform`] if its global transform hasn't been propagated yet.
+fn render_transform(
+    global_transforms: &bones::ComponentStore<bones::GlobalTransform>,
+    entity: bones::Entity,
+    transform: &bones::Transform,
+) -> bones::Transform {
+    global_transforms
+        .get(entity)
+        .map_or(*transform, |global| global.0)
+}
@@ -50,6 +50,42 @@ fn _generate_system_param_impl(input: TokenStream) -> syn::Result<TokenStream> {
             quote! { <#ty as ::bones_ecs::prelude::SystemParam>::get_state(world) }
         }));
 
+    let get_system_state_items: Punctuated<TokenStream, Token![,]> =
+        Punctuated::from_iter(fields.named.iter().map(|field| {
+            let ty = &field.ty;
+            quote! { <#ty as ::bones_ecs::prelude::SystemParam>::get_system_state(world, system) }
+        }));
+
+    let update_state_items = fields.named.iter().enumerate().map(|(index, field)| {
+        let ty = &field.ty;
+        let index = Index {
+            index: index as u32,
+            span: Span::call_site(),
+        };
+        quote! { <#ty as ::bones_ecs::prelude::SystemParam>::update_state(world, &mut state.#index); }
+    });
+
+    let change_world_items = fields.named.iter().enumerate().map(|(index, field)| {
+        let ty = &field.ty;
+        let index = Index {
+            index: index as u32,
+            span: Span::call_site(),
+        };
+        quote! {
+            <#ty as ::bones_ecs::prelude::SystemParam>::change_world(world, system, &mut state.#index);
+        }
+    });
+
+    let access_items = fields.named.iter().map(|field| {
+        let ty = &field.ty;
+        quote! { <#ty as ::bones_ecs::prelude::SystemParam>::access(access); }
+    });
+
+    let borrows_items = fields.named.iter().map(|field| {
+        let ty = &field.ty;
+        quote! { <#ty as ::bones_ecs::prelude::SystemParam>::borrows(borrows); }
+    });
+
     let borrow_param_fields: Punctuated<TokenStream, Token![,]> = fields
         .named
         .iter()
@@ -74,6 +110,28 @@ fn _generate_system_param_impl(input: TokenStream) -> syn::Result<TokenStream> {
             fn get_state(world: &::bones_ecs::prelude::World) -> Self::State {
                 ( #get_state_items )
             }
+            fn get_system_state(
+                world: &::bones_ecs::prelude::World,
+                system: ::bones_ecs::prelude::SystemId,
+            ) -> Self::State {
+                ( #get_system_state_items )
+            }
+            fn update_state(world: &::bones_ecs::prelude::World, state: &mut Self::State) {
+                #( #update_state_items )*
+            }
+            fn change_world(
+                world: &::bones_ecs::prelude::World,
+                system: ::bones_ecs::prelude::SystemId,
+                state: &mut Self::State,
+            ) {
+                #( #change_world_items )*
+            }
+            fn access(access: &mut ::bones_ecs::prelude::SystemAccess) {
+                #( #access_items )*
+            }
+            fn borrows(borrows: &mut ::bones_ecs::prelude::SystemAccess) {
+                #( #borrows_items )*
+            }
             fn borrow<'s>(
                 world: &'s ::bones_ecs::prelude::World,
                 state: &'s mut Self::State,
@@ -110,22 +168,51 @@ mod tests {
             impl<'a> ::bones_ecs::prelude::SystemParam for MySystemParam<'a> {
                 type State = (
                     <Commands<'a> as ::bones_ecs::prelude::SystemParam>::State,
-                    <ResMut<'a, Entities> as ::bones_ecs::prelude::SystemParam>::State
+                    <Res<'a, Entities> as ::bones_ecs::prelude::SystemParam>::State
                 );
                 type Param<'p> = MySystemParam<'p>;
                 fn get_state(world: &::bones_ecs::prelude::World) -> Self::State {
                     (
                         <Commands<'a> as ::bones_ecs::prelude::SystemParam>::get_state(world),
-                        <ResMut<'a, Entities> as ::bones_ecs::prelude::SystemParam>::get_state(world)
+                        <Res<'a, Entities> as ::bones_ecs::prelude::SystemParam>::get_state(world)
                     )
                 }
+                fn get_system_state(
+                    world: &::bones_ecs::prelude::World,
+                    system: ::bones_ecs::prelude::SystemId,
+                ) -> Self::State {
+                    (
+                        <Commands<'a> as ::bones_ecs::prelude::SystemParam>::get_system_state(world, system),
+                        <Res<'a, Entities> as ::bones_ecs::prelude::SystemParam>::get_system_state(world, system)
+                    )
+                }
+                fn update_state(world: &::bones_ecs::prelude::World, state: &mut Self::State) {
+                    <Commands<'a> as ::bones_ecs::prelude::SystemParam>::update_state(world, &mut state.0);
+                    <Res<'a, Entities> as ::bones_ecs::prelude::SystemParam>::update_state(world, &mut state.1);
+                }
+                fn change_world(
+                    world: &::bones_ecs::prelude::World,
+                    system: ::bones_ecs::prelude::SystemId,
+                    state: &mut Self::State,
+                ) {
+                    <Commands<'a> as ::bones_ecs::prelude::SystemParam>::change_world(world, system, &mut state.0);
+                    <Res<'a, Entities> as ::bones_ecs::prelude::SystemParam>::change_world(world, system, &mut state.1);
+                }
+                fn access(access: &mut ::bones_ecs::prelude::SystemAccess) {
+                    <Commands<'a> as ::bones_ecs::prelude::SystemParam>::access(access);
+                    <Res<'a, Entities> as ::bones_ecs::prelude::SystemParam>::access(access);
+                }
+                fn borrows(borrows: &mut ::bones_ecs::prelude::SystemAccess) {
+                    <Commands<'a> as ::bones_ecs::prelude::SystemParam>::borrows(borrows);
+                    <Res<'a, Entities> as ::bones_ecs::prelude::SystemParam>::borrows(borrows);
+                }
                 fn borrow<'s>(
                     world: &'s ::bones_ecs::prelude::World,
                     state: &'s mut Self::State,
                 ) -> Self::Param<'s> {
                     Self::Param {
                         commands: <Commands<'a> as ::bones_ecs::prelude::SystemParam>::borrow(world, &mut state.0),
-                        entities: <ResMut<'a, Entities> as ::bones_ecs::prelude::SystemParam>::borrow(world, &mut state.1)
+                        entities: <Res<'a, Entities> as ::bones_ecs::prelude::SystemParam>::borrow(world, &mut state.1)
                     }
                 }
             }
@@ -133,7 +220,7 @@ mod tests {
         let input = quote! {
             struct MySystemParam<'a> {
                 commands: Commands<'a>,
-                entities: ResMut<'a, Entities>,
+                entities: Res<'a, Entities>,
             }
         };
         let actual = generate_system_param_impl(input);
@@ -6,63 +6,237 @@
 
 use crate::prelude::*;
 
-// 2^32 gives  4 billion concurrent entities for 512MB   of ram per component
-// 2^24 gives 16 million concurrent entities for 2MB     of ram per component
-// 2^20 gives  1 million concurrent entities for 128KB   of ram per component
-// 2^16 gives 65536      concurrent entities for 8KB     of ram per component
-// 2^12 gives 4096       concurrent entities for 512B    of ram per component
-// SIMD processes 256 bits/entities (32 bytes) at once when comparing bitsets.
-#[cfg(feature = "keysize16")]
-const BITSET_EXP: u32 = 16;
-#[cfg(all(
-    feature = "keysize20",
-    not(feature = "keysize16"),
-    not(feature = "keysize24"),
-    not(feature = "keysize32")
-))]
-const BITSET_EXP: u32 = 20;
-#[cfg(all(
-    feature = "keysize24",
-    not(feature = "keysize16"),
-    not(feature = "keysize20"),
-    not(feature = "keysize32")
-))]
-const BITSET_EXP: u32 = 24;
-#[cfg(all(
-    feature = "keysize32",
-    not(feature = "keysize16"),
-    not(feature = "keysize20"),
-    not(feature = "keysize24")
-))]
-const BITSET_EXP: u32 = 32;
-
 pub use bitset_core::*;
 
-pub(crate) const BITSET_SIZE: usize = 2usize.saturating_pow(BITSET_EXP);
-pub(crate) const BITSET_SLICE_COUNT: usize = BITSET_SIZE / (32 * 8 / 8);
+/// The number of bits in each chunk of a [`BitSetVec`].
+const CHUNK_BITS: usize = 32 * 8;
 
 /// The type of bitsets used to track entities in component storages.
 /// Mostly used to create caches.
+///
+/// The bitset is stored as chunks of 256 bits, so that SIMD can process a whole chunk at once when
+/// comparing bitsets. It starts out empty and grows when bits past its end are set, so that its
+/// size follows the number of entities. Bits past the end of the bitset are unset, and operations
+/// between bitsets of different lengths treat the missing chunks as zeros.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Deref, DerefMut, Clone, Debug)]
+#[derive(Deref, DerefMut, Clone, Debug, Default)]
 pub struct BitSetVec(pub Vec<[u32; 8]>);
 
-impl Default for BitSetVec {
-    fn default() -> Self {
-        create_bitset()
-    }
-}
-
 impl BitSetVec {
     /// Check whether or not the bitset contains the given entity.
     #[inline]
     pub fn contains(&self, entity: Entity) -> bool {
         self.bit_test(entity.index() as usize)
     }
+
+    /// Get the index of the first set bit at or after the given one.
+    ///
+    /// Empty chunks are skipped as a whole, so this is cheap for sparse bitsets.
+    pub fn next_set_bit(&self, bit: usize) -> Option<usize> {
+        let mut chunk = bit / CHUNK_BITS;
+        let mut word = bit % CHUNK_BITS / 32;
+        let mut mask = !0u32 << (bit % 32);
+        while let Some(words) = self.0.get(chunk) {
+            if word == 0 && !words.bit_any() {
+                chunk += 1;
+                continue;
+            }
+            for (i, value) in words.iter().enumerate().skip(word) {
+                let value = value & mask;
+                if value != 0 {
+                    return Some(chunk * CHUNK_BITS + i * 32 + value.trailing_zeros() as usize);
+                }
+                mask = !0;
+            }
+            chunk += 1;
+            word = 0;
+            mask = !0;
+        }
+        None
+    }
+
+    /// Remove the chunks at the end of the bitset that have no bits set.
+    ///
+    /// This doesn't change which bits are set, and is called for the entity and component bitsets
+    /// in [`World::maintain()`].
+    pub fn shrink(&mut self) {
+        let len = self
+            .0
+            .iter()
+            .rposition(|chunk| chunk.bit_any())
+            .map_or(0, |i| i + 1);
+        self.0.truncate(len);
+    }
+
+    /// Whether or not there are chunks at the end of the bitset that [`shrink()`][Self::shrink]
+    /// would remove.
+    pub fn can_shrink(&self) -> bool {
+        self.0.last().is_some_and(|chunk| !chunk.bit_any())
+    }
+
+    /// Make sure that the bitset has at least `len` chunks.
+    #[inline]
+    fn grow(&mut self, len: usize) {
+        if self.0.len() < len {
+            self.0.resize(len, [0; 8]);
+        }
+    }
+}
+
+impl BitSet for BitSetVec {
+    #[inline]
+    fn bit_len(&self) -> usize {
+        self.0.len() * CHUNK_BITS
+    }
+
+    #[inline]
+    fn bit_init(&mut self, value: bool) -> &mut Self {
+        self.0.bit_init(value);
+        self
+    }
+
+    #[inline]
+    fn bit_test(&self, bit: usize) -> bool {
+        self.0
+            .get(bit / CHUNK_BITS)
+            .is_some_and(|chunk| chunk.bit_test(bit % CHUNK_BITS))
+    }
+
+    #[inline]
+    fn bit_set(&mut self, bit: usize) -> &mut Self {
+        self.grow(bit / CHUNK_BITS + 1);
+        self.0.bit_set(bit);
+        self
+    }
+
+    #[inline]
+    fn bit_reset(&mut self, bit: usize) -> &mut Self {
+        if let Some(chunk) = self.0.get_mut(bit / CHUNK_BITS) {
+            chunk.bit_reset(bit % CHUNK_BITS);
+        }
+        self
+    }
+
+    #[inline]
+    fn bit_flip(&mut self, bit: usize) -> &mut Self {
+        self.grow(bit / CHUNK_BITS + 1);
+        self.0.bit_flip(bit);
+        self
+    }
+
+    fn bit_all(&self) -> bool {
+        self.0.bit_all()
+    }
+
+    fn bit_any(&self) -> bool {
+        self.0.bit_any()
+    }
+
+    fn bit_eq(&self, rhs: &Self) -> bool {
+        let (short, long) = if self.len() < rhs.len() {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+        short.0 == long.0[..short.len()] && !long.0[short.len()..].bit_any()
+    }
+
+    fn bit_disjoint(&self, rhs: &Self) -> bool {
+        self.0.bit_disjoint(&rhs.0)
+    }
+
+    fn bit_subset(&self, rhs: &Self) -> bool {
+        let len = self.len().min(rhs.len());
+        self.0[..len].bit_subset(&rhs.0[..len]) && !self.0[len..].bit_any()
+    }
+
+    fn bit_or(&mut self, rhs: &Self) -> &mut Self {
+        self.grow(rhs.len());
+        self.0.bit_or(&rhs.0);
+        self
+    }
+
+    fn bit_and(&mut self, rhs: &Self) -> &mut Self {
+        self.0.truncate(rhs.len());
+        self.0.bit_and(&rhs.0);
+        self
+    }
+
+    fn bit_andnot(&mut self, rhs: &Self) -> &mut Self {
+        self.0.bit_andnot(&rhs.0);
+        self
+    }
+
+    fn bit_xor(&mut self, rhs: &Self) -> &mut Self {
+        self.grow(rhs.len());
+        self.0.bit_xor(&rhs.0);
+        self
+    }
+
+    /// Flip every bit within the current length of the bitset.
+    ///
+    /// Bits past the end of the bitset stay unset, so use [`bit_andnot()`][Self::bit_andnot]
+    /// instead of `bit_not()` followed by [`bit_and()`][Self::bit_and] to exclude a bitset from
+    /// another.
+    fn bit_not(&mut self) -> &mut Self {
+        self.0.bit_not();
+        self
+    }
+
+    fn bit_mask(&mut self, rhs: &Self, mask: &Self) -> &mut Self {
+        self.grow(rhs.len().min(mask.len()));
+        let len = self.len().min(mask.len());
+        for (i, chunk) in self.0[..len].iter_mut().enumerate() {
+            let value = rhs.0.get(i).copied().unwrap_or_default();
+            chunk.bit_mask(&value, &mask.0[i]);
+        }
+        self
+    }
+
+    fn bit_count(&self) -> usize {
+        self.0.bit_count()
+    }
 }
 
-/// Creates a bitset big enough to contain the index of each entity.
+/// Creates an empty bitset, which grows to contain the index of each entity as bits are set.
 /// Mostly used to create caches.
 pub fn create_bitset() -> BitSetVec {
-    BitSetVec(vec![[0u32; 8]; BITSET_SLICE_COUNT])
+    BitSetVec::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitset(bits: &[usize]) -> BitSetVec {
+        let mut bitset = BitSetVec::default();
+        for &bit in bits {
+            bitset.bit_set(bit);
+        }
+        bitset
+    }
+
+    #[test]
+    fn growable_bitset() {
+        let mut a = bitset(&[1, 300, 70_000]);
+        assert_eq!(a.len(), 70_000 / 256 + 1);
+        assert!(a.bit_test(70_000) && !a.bit_test(70_001) && !a.bit_test(1 << 30));
+        assert_eq!(a.next_set_bit(2), Some(300));
+        assert_eq!(a.next_set_bit(301), Some(70_000));
+        assert_eq!(a.next_set_bit(70_001), None);
+
+        // Bitsets of different lengths are joined as if the shorter one was padded with zeros.
+        let b = bitset(&[1, 300]);
+        assert!(a.clone().bit_and(&b).bit_eq(&b));
+        assert!(a.clone().bit_andnot(&b).bit_eq(&bitset(&[70_000])));
+        assert!(b.clone().bit_or(&a).bit_eq(&a));
+        assert!(b.bit_subset(&a) && !a.bit_subset(&b));
+
+        // Shrinking removes the trailing empty chunks.
+        a.bit_reset(70_000);
+        assert!(a.can_shrink());
+        a.shrink();
+        assert_eq!(a.len(), 2);
+        assert!(a.bit_eq(&b) && !a.can_shrink());
+    }
 }
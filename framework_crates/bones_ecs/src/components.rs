@@ -3,7 +3,7 @@
 use once_map::OnceMap;
 use std::sync::Arc;
 
-use crate::prelude::*;
+use crate::{prelude::*, system::ChangeTicks};
 
 mod iterator;
 mod typed;
@@ -25,6 +25,8 @@ pub type UntypedAtomicComponentStore = Arc<AtomicCell<UntypedComponentStore>>;
 #[derive(Default)]
 pub struct ComponentStores {
     pub(crate) components: OnceMap<SchemaId, UntypedAtomicComponentStore>,
+    /// The change detection ticks of the world, shared with all of the stores.
+    pub(crate) ticks: Arc<ChangeTicks>,
 }
 
 // SOUND: all of the functions for ComponentStores requires that the types stored implement Sync +
@@ -34,15 +36,23 @@ unsafe impl Send for ComponentStores {}
 
 impl Clone for ComponentStores {
     fn clone(&self) -> Self {
+        // Copy the ticks, so that the change detection of the clone is independent of ours.
+        let ticks = Arc::new((*self.ticks).clone());
         Self {
             components: self
                 .components
                 .read_only_view()
                 .iter()
-                // Be sure to clone the inner data of the components, so we don't just end up with
-                // new `Arc`s pointing to the same data.
-                .map(|(&k, v)| (k, Arc::new((**v).clone())))
+                // Be sure to clone the inner stores, so we don't just end up with new `Arc`s
+                // pointing to the same cells. This is cheap because the component data itself is
+                // copy-on-write, see [`UntypedComponentStore`].
+                .map(|(&k, v)| {
+                    let mut store = v.borrow().clone();
+                    store.world_ticks = ticks.clone();
+                    (k, Arc::new(AtomicCell::new(store)))
+                })
                 .collect(),
+            ticks,
         }
     }
 }
@@ -80,7 +90,7 @@ impl ComponentStores {
     /// Get the untyped component storage by the component's [`SchemaId`].
     pub fn get_by_schema(&self, schema: &'static Schema) -> &AtomicCell<UntypedComponentStore> {
         self.components.insert(schema.id(), |_| {
-            Arc::new(AtomicCell::new(UntypedComponentStore::new(schema)))
+            Arc::new(AtomicCell::new(self.new_store(schema)))
         })
     }
 
@@ -91,10 +101,17 @@ impl ComponentStores {
     ) -> Arc<AtomicCell<UntypedComponentStore>> {
         self.components.map_insert(
             schema.id(),
-            |_| Arc::new(AtomicCell::new(UntypedComponentStore::new(schema))),
+            |_| Arc::new(AtomicCell::new(self.new_store(schema))),
             |_key, value| value.clone(),
         )
     }
+
+    /// Create a new store for the world, sharing its change detection ticks.
+    fn new_store(&self, schema: &'static Schema) -> UntypedComponentStore {
+        let mut store = UntypedComponentStore::new(schema);
+        store.world_ticks = self.ticks.clone();
+        store
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::{marker::PhantomData, rc::Rc};
 
 use crate::prelude::*;
 
@@ -30,7 +30,7 @@ pub type ComponentBitsetOptionalIteratorMut<'a, T> = std::iter::Map<
 /// iterator will fetch data from the storage at index i and return it.
 pub struct UntypedComponentBitsetIterator<'a> {
     pub(crate) current_id: usize,
-    pub(crate) components: &'a UntypedComponentStore,
+    pub(crate) components: &'a ComponentData,
     pub(crate) bitset: Rc<BitSetVec>,
 }
 
@@ -38,25 +38,23 @@ impl<'a> Iterator for UntypedComponentBitsetIterator<'a> {
     type Item = SchemaRef<'a>;
     fn next(&mut self) -> Option<Self::Item> {
         let max_id = self.components.max_id;
-        while !(self.bitset.bit_test(self.current_id)
-            && self.components.bitset.bit_test(self.current_id))
-            && self.current_id <= max_id
-        {
-            self.current_id += 1;
+        while let Some(id) = self.bitset.next_set_bit(self.current_id) {
+            if id >= max_id {
+                break;
+            }
+            self.current_id = id + 1;
+            if self.components.bitset.bit_test(id) {
+                // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
+                return Some(unsafe {
+                    SchemaRef::from_ptr_schema(
+                        self.components.storage.unchecked_idx(id),
+                        self.components.schema,
+                    )
+                });
+            }
         }
-        let ret = if self.current_id <= max_id {
-            // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
-            Some(unsafe {
-                SchemaRef::from_ptr_schema(
-                    self.components.storage.unchecked_idx(self.current_id),
-                    self.components.schema,
-                )
-            })
-        } else {
-            None
-        };
-        self.current_id += 1;
-        ret
+        self.current_id = max_id;
+        None
     }
 }
 
@@ -71,30 +69,22 @@ impl<'a> Iterator for UntypedComponentOptionalBitsetIterator<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         // We stop iterating at bitset length, not component store length, as we want to iterate over
         // whole bitset and return None for entities that don't have this optional component.
-        let max_id = self.bitset.bit_len() - 1;
-        while !self.bitset.bit_test(self.current_id) && self.current_id <= max_id {
-            self.current_id += 1;
-        }
-        let ret = if self.current_id <= max_id {
-            // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
-            if self.components.bitset.bit_test(self.current_id) {
-                Some(Some(unsafe {
-                    SchemaRef::from_ptr_schema(
-                        self.components.storage.unchecked_idx(self.current_id),
-                        self.components.schema,
-                    )
-                }))
-            } else {
-                // Component at current_id is not in store, however we are still iterating,
-                // later ids in self.bitset may have components in store.
-                Some(None)
-            }
+        // Iterated through whole bitset
+        let id = self.bitset.next_set_bit(self.current_id)?;
+        self.current_id = id + 1;
+        // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
+        if self.components.bitset.bit_test(id) {
+            Some(Some(unsafe {
+                SchemaRef::from_ptr_schema(
+                    self.components.storage.unchecked_idx(id),
+                    self.components.schema,
+                )
+            }))
         } else {
-            // Iterated through whole bitset
-            None
-        };
-        self.current_id += 1;
-        ret
+            // Component at id is not in store, however we are still iterating, later ids in
+            // self.bitset may have components in store.
+            Some(None)
+        }
     }
 }
 
@@ -102,7 +92,8 @@ impl<'a> Iterator for UntypedComponentOptionalBitsetIterator<'a> {
 /// iterator will fetch data from the storage at index i.
 pub struct UntypedComponentBitsetIteratorMut<'a> {
     pub(crate) current_id: usize,
-    pub(crate) components: &'a mut UntypedComponentStore,
+    pub(crate) components: &'a mut ComponentData,
+    pub(crate) tick: u64,
     pub(crate) bitset: Rc<BitSetVec>,
 }
 
@@ -110,26 +101,25 @@ impl<'a> Iterator for UntypedComponentBitsetIteratorMut<'a> {
     type Item = SchemaRefMut<'a>;
     fn next(&mut self) -> Option<Self::Item> {
         let max_id = self.components.max_id;
-        while !(self.bitset.bit_test(self.current_id)
-            && self.components.bitset.bit_test(self.current_id))
-            && self.current_id <= max_id
-        {
-            self.current_id += 1;
+        while let Some(id) = self.bitset.next_set_bit(self.current_id) {
+            if id >= max_id {
+                break;
+            }
+            self.current_id = id + 1;
+            if self.components.bitset.bit_test(id) {
+                self.components.ticks[id].changed = self.tick;
+                // SAFE: We know that the index is within bounds, and we know that the pointer will
+                // be valid for the new lifetime.
+                return Some(unsafe {
+                    SchemaRefMut::from_ptr_schema(
+                        self.components.storage.unchecked_idx(id),
+                        self.components.schema,
+                    )
+                });
+            }
         }
-        let ret = if self.current_id <= max_id {
-            // SAFE: We know that the index is within bounds, and we know that the pointer will be
-            // valid for the new lifetime.
-            Some(unsafe {
-                SchemaRefMut::from_ptr_schema(
-                    self.components.storage.unchecked_idx(self.current_id),
-                    self.components.schema,
-                )
-            })
-        } else {
-            None
-        };
-        self.current_id += 1;
-        ret
+        self.current_id = max_id;
+        None
     }
 }
 
@@ -144,30 +134,93 @@ impl<'a> Iterator for UntypedComponentOptionalBitsetIteratorMut<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         // We do not stop iterating at component store length, as we want to iterate over
         // whole bitset and return None for entities that don't have this optional component.
-        let max_id = self.bitset.bit_len() - 1;
-        while !self.bitset.bit_test(self.current_id) && self.current_id <= max_id {
-            self.current_id += 1;
-        }
-        let ret = if self.current_id <= max_id {
-            // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
-            if self.components.bitset.bit_test(self.current_id) {
-                Some(Some(unsafe {
-                    SchemaRefMut::from_ptr_schema(
-                        self.components.storage.unchecked_idx(self.current_id),
-                        self.components.schema,
-                    )
-                }))
-            } else {
-                // Component at current_id is not in store, however we are still iterating,
-                // later ids in self.bitset may have components in store.
-                Some(None)
-            }
+        // Iterated through whole bitset
+        let id = self.bitset.next_set_bit(self.current_id)?;
+        self.current_id = id + 1;
+        // SAFE: Here we are just getting a pointer, not doing anything unsafe with it.
+        if self.components.bitset.bit_test(id) {
+            self.components.ticks[id].changed = self.tick;
+            Some(Some(unsafe {
+                SchemaRefMut::from_ptr_schema(
+                    self.components.storage.unchecked_idx(id),
+                    self.components.schema,
+                )
+            }))
         } else {
-            // Iterated through whole bitset
-            None
-        };
-        self.current_id += 1;
-        ret
+            // Component at id is not in store, however we are still iterating, later ids in
+            // self.bitset may have components in store.
+            Some(None)
+        }
+    }
+}
+
+/// Shared access to the components of a store, used to get the components of entities from
+/// several threads with [`Entities::par_iter_with()`].
+pub struct ParComponents<'a, T> {
+    data: &'a ComponentData,
+    _phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T: HasSchema> ParComponents<'a, T> {
+    /// Get shared access to the components of the store.
+    pub fn new(store: &'a ComponentStore<T>) -> Self {
+        Self {
+            data: &store.as_untyped().data,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get the component of the entity at the given index, if it has one.
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        // SAFE: we ensure that there is allocated storage for entities that have their bit set,
+        // and we know that the schema matches.
+        self.data
+            .bitset
+            .bit_test(index)
+            .then(|| unsafe { &*self.data.storage.unchecked_idx(index).cast::<T>() })
+    }
+}
+
+/// Mutable access to the components of a store, used to borrow the components of different
+/// entities mutably from several threads with [`Entities::par_iter_with()`].
+pub struct ParComponentsMut<'a, T> {
+    data: &'a ComponentData,
+    ticks: *mut ComponentTicks,
+    tick: u64,
+    _phantom: PhantomData<&'a mut T>,
+}
+
+// SOUND: the components are only borrowed mutably through `get()`, which requires that the
+// components of each entity are only borrowed once.
+unsafe impl<T: HasSchema> Sync for ParComponentsMut<'_, T> {}
+unsafe impl<T: HasSchema> Send for ParComponentsMut<'_, T> {}
+
+impl<'a, T: HasSchema> ParComponentsMut<'a, T> {
+    /// Get mutable access to the components of the store.
+    pub fn new(store: &'a mut ComponentStore<T>) -> Self {
+        let store = store.as_untyped_mut();
+        let tick = store.system_ticks().this_run;
+        let data = store.data_mut();
+        Self {
+            ticks: data.ticks.as_mut_ptr(),
+            data,
+            tick,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Borrow the component of the entity at the given index mutably, if it has one.
+    ///
+    /// # Safety
+    ///
+    /// This must not be called more than once for the same index, so that there is only one
+    /// mutable borrow of each component.
+    pub unsafe fn get(&self, index: usize) -> Option<&'a mut T> {
+        if !self.data.bitset.bit_test(index) {
+            return None;
+        }
+        (*self.ticks.add(index)).changed = self.tick;
+        Some(&mut *self.data.storage.unchecked_idx(index).cast::<T>())
     }
 }
 
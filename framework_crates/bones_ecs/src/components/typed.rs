@@ -24,7 +24,7 @@ impl<T: HasSchema> TryFrom<UntypedComponentStore> for ComponentStore<T> {
     type Error = SchemaMismatchError;
 
     fn try_from(untyped: UntypedComponentStore) -> Result<Self, Self::Error> {
-        if untyped.schema == T::schema() {
+        if untyped.schema() == T::schema() {
             Ok(Self {
                 untyped,
                 _phantom: PhantomData,
@@ -42,6 +42,20 @@ impl<T: HasSchema> ComponentStore<T> {
         self.untyped
     }
 
+    /// Get the internal, untyped [`ComponentStore`].
+    #[inline]
+    pub fn as_untyped(&self) -> &UntypedComponentStore {
+        &self.untyped
+    }
+
+    /// Get the internal, untyped [`ComponentStore`] mutably.
+    ///
+    /// This is not public, because changing the schema of the untyped store would be unsound.
+    #[inline]
+    pub(crate) fn as_untyped_mut(&mut self) -> &mut UntypedComponentStore {
+        &mut self.untyped
+    }
+
     /// Creates a [`ComponentStore`] from an [`UntypedComponentStore`].
     /// # Panics
     /// Panics if the schema doesn't match `T`.
@@ -109,6 +123,50 @@ impl<T: HasSchema> ComponentStore<T> {
         self.untyped.remove(entity)
     }
 
+    /// Get the change detection ticks for the component of the given `Entity`, if it has one.
+    #[inline]
+    pub fn ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        self.untyped.ticks(entity)
+    }
+
+    /// Returns whether or not the component of `Entity` was added since the running system last
+    /// ran.
+    ///
+    /// See [`SystemTicks`].
+    #[inline]
+    pub fn is_added(&self, entity: Entity) -> bool {
+        self.untyped.is_added(entity)
+    }
+
+    /// Returns whether or not the component of `Entity` was inserted or mutably borrowed since the
+    /// running system last ran.
+    ///
+    /// See [`SystemTicks`].
+    #[inline]
+    pub fn is_changed(&self, entity: Entity) -> bool {
+        self.untyped.is_changed(entity)
+    }
+
+    /// Get the entities that had this component removed during the current or previous update.
+    #[inline]
+    pub fn removed(&self) -> &Events<Entity> {
+        self.untyped.removed()
+    }
+
+    /// Returns a bitset of the entities that had this component added since the running system
+    /// last ran.
+    #[inline]
+    pub fn added_bitset(&self) -> BitSetVec {
+        self.untyped.added_bitset()
+    }
+
+    /// Returns a bitset of the entities that had this component inserted or mutably borrowed since
+    /// the running system last ran.
+    #[inline]
+    pub fn changed_bitset(&self) -> BitSetVec {
+        self.untyped.changed_bitset()
+    }
+
     /// Iterates immutably over all components of this type.
     /// Very fast but doesn't allow joining with other component types.
     #[inline]
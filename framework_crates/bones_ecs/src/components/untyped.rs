@@ -6,22 +6,71 @@ use std::{
     mem::MaybeUninit,
     ptr::{self},
     rc::Rc,
+    sync::Arc,
 };
 
+/// The change detection ticks for a single component.
+///
+/// Components are marked with the tick of the system that inserts or mutably borrows them. See
+/// [`SystemTicks`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComponentTicks {
+    /// The tick that the component was added at.
+    pub added: u64,
+    /// The tick that the component was last inserted or mutably borrowed at.
+    pub changed: u64,
+}
+
 /// Holds components of a given type indexed by `Entity`.
 ///
 /// We do not check if the given entity is alive here, this should be done using `Entities`.
+///
+/// The component data is copy-on-write: cloning the store is cheap because the clone shares the
+/// data with the original, and the data is only copied the first time that either store is
+/// modified. This keeps world snapshots proportional to what changed since the last one.
+///
+/// If [`ComponentHooks`] have been registered for the store, it also records the components that
+/// are inserted, replaced or removed, so that the hooks can be run later.
+///
+/// The entities whose components were removed are kept for two updates, so that they can be read
+/// by systems with [`RemovedComponents`].
 pub struct UntypedComponentStore {
+    pub(crate) data: Arc<ComponentData>,
+    pub(crate) hooks: Arc<ComponentHooks>,
+    pub(crate) hook_events: Vec<(crate::hooks::HookEventOrder, ComponentHookKind, Entity)>,
+    pub(crate) removed: Events<Entity>,
+    pub(crate) changes: crate::query::ChangeLog,
+    /// The change detection ticks of the world that the store belongs to.
+    pub(crate) world_ticks: Arc<crate::system::ChangeTicks>,
+}
+
+/// The component data of an [`UntypedComponentStore`], which may be shared between clones of the
+/// store.
+pub(crate) struct ComponentData {
     pub(crate) bitset: BitSetVec,
     pub(crate) storage: ResizableAlloc,
+    pub(crate) ticks: Vec<ComponentTicks>,
     pub(crate) max_id: usize,
     pub(crate) schema: &'static Schema,
 }
 
-unsafe impl Sync for UntypedComponentStore {}
-unsafe impl Send for UntypedComponentStore {}
+unsafe impl Sync for ComponentData {}
+unsafe impl Send for ComponentData {}
 
 impl Clone for UntypedComponentStore {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            hooks: self.hooks.clone(),
+            hook_events: self.hook_events.clone(),
+            removed: self.removed.clone(),
+            changes: self.changes.clone(),
+            world_ticks: self.world_ticks.clone(),
+        }
+    }
+}
+
+impl Clone for ComponentData {
     fn clone(&self) -> Self {
         let size = self.schema.layout().size();
         let new_storage = self.storage.clone();
@@ -49,13 +98,14 @@ impl Clone for UntypedComponentStore {
         Self {
             bitset: self.bitset.clone(),
             storage: new_storage,
+            ticks: self.ticks.clone(),
             max_id: self.max_id,
             schema: self.schema,
         }
     }
 }
 
-impl Drop for UntypedComponentStore {
+impl Drop for ComponentData {
     fn drop(&mut self) {
         if let Some(drop_fn) = &self.schema.drop_fn {
             for i in 0..self.storage.capacity() {
@@ -74,6 +124,65 @@ impl Drop for UntypedComponentStore {
     }
 }
 
+impl ComponentData {
+    fn new(schema: &'static Schema) -> Self {
+        Self {
+            bitset: create_bitset(),
+            storage: ResizableAlloc::new(schema.layout()),
+            ticks: Vec::new(),
+            max_id: 0,
+            schema,
+        }
+    }
+
+    /// Ensures that we have the storage filled at least until the `until` variable.
+    ///
+    /// Usually, set this to `entity.index`.
+    fn allocate_enough(&mut self, until: usize) {
+        if self.storage.capacity() <= until {
+            self.storage
+                // TODO: Determine a better policy for resizing and pre-allocating component storage.
+                // Right now we double the size of the storage every time we run out. It seems like we
+                // might be able to come up with a smarter policy. On top of that we should
+                // be able to create a type data for components ( see
+                // `bones_framework::metadata_asset()` for example ) that lets you customize the resize
+                // and also pre-allocation strategy for the component. Right now we don't pre-allocate
+                // any memory, but that could be useful for components that know there will be a lot of
+                // them, such as bullets.
+                .resize((until + 1) * 2)
+                .unwrap();
+        }
+        if self.ticks.len() <= until {
+            self.ticks
+                .resize(self.storage.capacity(), ComponentTicks::default());
+        }
+    }
+
+    fn get_idx(&self, idx: usize) -> Option<SchemaRef> {
+        if self.bitset.bit_test(idx) {
+            // SOUND: we ensure that there is allocated storge for entities that have their bit set.
+            let ptr = unsafe { self.storage.unchecked_idx(idx) };
+            // SOUND: we know that the pointer has our schema.
+            Some(unsafe { SchemaRef::from_ptr_schema(ptr, self.schema) })
+        } else {
+            None
+        }
+    }
+
+    fn get_idx_mut<'a>(&mut self, idx: usize, tick: u64) -> Option<SchemaRefMut<'a>> {
+        if self.bitset.bit_test(idx) {
+            self.ticks[idx].changed = tick;
+            // SOUND: we ensure that there is allocated storage for entities that have their bit
+            // set.
+            let ptr = unsafe { self.storage.unchecked_idx(idx) };
+            // SOUND: we know that the pointer has our schema.
+            Some(unsafe { SchemaRefMut::from_ptr_schema(ptr, self.schema) })
+        } else {
+            None
+        }
+    }
+}
+
 impl UntypedComponentStore {
     /// Create a arbitrary [`UntypedComponentStore`].
     ///
@@ -81,26 +190,150 @@ impl UntypedComponentStore {
     /// typed [`ComponentStore<T>`] instead.
     pub fn new(schema: &'static Schema) -> Self {
         Self {
-            bitset: create_bitset(),
-            storage: ResizableAlloc::new(schema.layout()),
-            max_id: 0,
-            schema,
+            data: Arc::new(ComponentData::new(schema)),
+            hooks: default(),
+            hook_events: Vec::new(),
+            removed: default(),
+            changes: default(),
+            world_ticks: default(),
         }
     }
 
     /// Create an [`UntypedComponentStore`] that is valid for the given type `T`.
     pub fn for_type<T: HasSchema>() -> Self {
-        Self {
-            bitset: create_bitset(),
-            storage: ResizableAlloc::new(T::schema().layout()),
-            max_id: 0,
-            schema: T::schema(),
-        }
+        Self::new(T::schema())
+    }
+
+    /// Get the component data for modification, copying it first if it is shared with a clone of
+    /// this store.
+    #[inline]
+    pub(crate) fn data_mut(&mut self) -> &mut ComponentData {
+        Arc::make_mut(&mut self.data)
+    }
+
+    /// Get the ticks of the system running on the current thread, see [`SystemTicks`].
+    #[inline]
+    pub(crate) fn system_ticks(&self) -> SystemTicks {
+        SystemTicks::current(&self.world_ticks)
     }
 
     /// Get the schema of the components stored.
     pub fn schema(&self) -> &'static Schema {
-        self.schema
+        self.data.schema
+    }
+
+    /// Get the hooks that are run when components are inserted, replaced or removed.
+    pub fn hooks(&self) -> &ComponentHooks {
+        &self.hooks
+    }
+
+    /// Add a hook that is run when a component is inserted, replaced or removed.
+    ///
+    /// See [`World::add_component_hook()`].
+    pub fn add_hook(&mut self, kind: ComponentHookKind, hook: ComponentHook) {
+        Arc::make_mut(&mut self.hooks).get_mut(kind).push(hook);
+    }
+
+    /// Record a hook event if there are hooks for it.
+    #[inline]
+    fn record_hook_event(&mut self, kind: ComponentHookKind, entity: Entity) {
+        if !self.hooks.get(kind).is_empty() {
+            self.hook_events
+                .push((crate::hooks::HookEventOrder::next(), kind, entity));
+        }
+    }
+
+    /// Get the entities whose components were removed during the current or previous update,
+    /// including the components removed because the entity was killed.
+    pub fn removed(&self) -> &Events<Entity> {
+        &self.removed
+    }
+
+    /// Drop the removals and the changes of the previous update, and start a new one.
+    ///
+    /// This is called automatically by [`World::maintain()`].
+    pub fn update(&mut self) {
+        self.removed.update();
+        self.changes.update();
+    }
+
+    /// Remove the unused memory at the end of the store's bitset.
+    ///
+    /// This is called automatically by [`World::maintain()`].
+    pub fn shrink(&mut self) {
+        // Avoid copying data that is shared with clones of the store when there is nothing to do.
+        if self.data.bitset.can_shrink() {
+            self.data_mut().bitset.shrink();
+        }
+    }
+
+    /// Get the change detection ticks for the component of the given [`Entity`], if it has one.
+    pub fn ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        let idx = entity.index() as usize;
+        self.data.bitset.bit_test(idx).then(|| self.data.ticks[idx])
+    }
+
+    /// Returns whether or not the entity's component was added since the running system last ran.
+    ///
+    /// See [`SystemTicks`].
+    pub fn is_added(&self, entity: Entity) -> bool {
+        self.ticks(entity)
+            .is_some_and(|ticks| self.system_ticks().is_newer(ticks.added))
+    }
+
+    /// Returns whether or not the entity's component was inserted or mutably borrowed since the
+    /// running system last ran.
+    ///
+    /// See [`SystemTicks`].
+    pub fn is_changed(&self, entity: Entity) -> bool {
+        self.ticks(entity)
+            .is_some_and(|ticks| self.system_ticks().is_newer(ticks.changed))
+    }
+
+    /// Returns a bitset of the entities that had their component added since the running system
+    /// last ran.
+    pub fn added_bitset(&self) -> BitSetVec {
+        let mut bitset = self.data.bitset.clone();
+        self.retain_added(&mut bitset);
+        bitset
+    }
+
+    /// Returns a bitset of the entities that had their component inserted or mutably borrowed
+    /// since the running system last ran.
+    pub fn changed_bitset(&self) -> BitSetVec {
+        let mut bitset = self.data.bitset.clone();
+        self.retain_changed(&mut bitset);
+        bitset
+    }
+
+    /// Unset the bits of the entities that didn't have their component added since the running
+    /// system last ran.
+    ///
+    /// Only the bits that are set are visited, so this is cheap when filtering a bitset that has
+    /// already been narrowed down by other components.
+    pub fn retain_added(&self, bitset: &mut BitSetVec) {
+        self.retain_newer(bitset, |ticks| ticks.added);
+    }
+
+    /// Unset the bits of the entities that didn't have their component inserted or mutably
+    /// borrowed since the running system last ran.
+    ///
+    /// Only the bits that are set are visited, so this is cheap when filtering a bitset that has
+    /// already been narrowed down by other components.
+    pub fn retain_changed(&self, bitset: &mut BitSetVec) {
+        self.retain_newer(bitset, |ticks| ticks.changed);
+    }
+
+    fn retain_newer(&self, bitset: &mut BitSetVec, get_tick: impl Fn(&ComponentTicks) -> u64) {
+        bitset.bit_and(&self.data.bitset);
+        let system_ticks = self.system_ticks();
+        let mut next = 0;
+        while let Some(i) = bitset.next_set_bit(next) {
+            next = i + 1;
+            if !system_ticks.is_newer(get_tick(&self.data.ticks[i])) {
+                bitset.bit_reset(i);
+            }
+        }
     }
 
     /// Insert component data for the given entity and get the previous component data if present.
@@ -120,7 +353,7 @@ impl UntypedComponentStore {
         entity: Entity,
         data: SchemaBox,
     ) -> Result<Option<SchemaBox>, SchemaMismatchError> {
-        if self.schema != data.schema() {
+        if self.schema() != data.schema() {
             Err(SchemaMismatchError)
         } else {
             let ptr = data.as_ptr();
@@ -154,7 +387,7 @@ impl UntypedComponentStore {
         entity: Entity,
         mut data: T,
     ) -> Result<Option<T>, SchemaMismatchError> {
-        if self.schema != T::schema() {
+        if self.schema() != T::schema() {
             Err(SchemaMismatchError)
         } else {
             let ptr = &mut data as *mut T as *mut c_void;
@@ -180,31 +413,55 @@ impl UntypedComponentStore {
     /// - If `false` is returned you must ensure the `data` pointer is not used after pushing.
     pub unsafe fn insert_raw(&mut self, entity: Entity, data: *mut c_void) -> bool {
         let index = entity.index() as usize;
-        let size = self.schema.layout().size();
+        let size = self.schema().layout().size();
+        let tick = self.system_ticks().this_run;
+        let had_component = self.data.bitset.bit_test(index);
+        self.record_hook_event(
+            if had_component {
+                ComponentHookKind::Replace
+            } else {
+                ComponentHookKind::Insert
+            },
+            entity,
+        );
+        if !had_component {
+            self.changes.push(index);
+        }
+        let store = self.data_mut();
 
         // If the component already exists on the entity
-        if self.bitset.bit_test(entity.index() as usize) {
-            let ptr = self.storage.unchecked_idx(index);
+        if had_component {
+            let ptr = store.storage.unchecked_idx(index);
 
             // Swap the data with the data already there
             ptr::swap_nonoverlapping(ptr, data, size);
 
+            // Mark the component as changed
+            store.ticks[index].changed = tick;
+
             // There was already a component of this type
             true
 
         // If the component does not already exist for this entity.
         } else {
             // Update our maximum enitity id.
-            self.max_id = self.max_id.max(index + 1);
+            store.max_id = store.max_id.max(index + 1);
 
             // Make sure we have enough memory allocated for storage.
-            self.allocate_enough(index);
+            store.allocate_enough(index);
 
             // Set the bit indicating that this entity has this component data stored.
-            self.bitset.bit_set(index);
+            store.bitset.bit_set(index);
+
+            // Mark the component as added
+            store.ticks[index] = ComponentTicks {
+                added: tick,
+                changed: tick,
+            };
 
             // Copy the data from the data pointer into our storage
-            self.storage
+            store
+                .storage
                 .unchecked_idx(index)
                 .copy_from_nonoverlapping(data, size);
 
@@ -213,25 +470,6 @@ impl UntypedComponentStore {
         }
     }
 
-    /// Ensures that we have the storage filled at least until the `until` variable.
-    ///
-    /// Usually, set this to `entity.index`.
-    fn allocate_enough(&mut self, until: usize) {
-        if self.storage.capacity() <= until {
-            self.storage
-                // TODO: Determine a better policy for resizing and pre-allocating component storage.
-                // Right now we double the size of the storage every time we run out. It seems like we
-                // might be able to come up with a smarter policy. On top of that we should
-                // be able to create a type data for components ( see
-                // `bones_framework::metadata_asset()` for example ) that lets you customize the resize
-                // and also pre-allocation strategy for the component. Right now we don't pre-allocate
-                // any memory, but that could be useful for components that know there will be a lot of
-                // them, such as bullets.
-                .resize((until + 1) * 2)
-                .unwrap();
-        }
-    }
-
     /// Get a reference to the component storage for the given [`Entity`].
     /// # Panics
     /// Panics if the schema of `T` doesn't match.
@@ -257,14 +495,7 @@ impl UntypedComponentStore {
     }
 
     fn get_idx(&self, idx: usize) -> Option<SchemaRef> {
-        if self.bitset.bit_test(idx) {
-            // SOUND: we ensure that there is allocated storge for entities that have their bit set.
-            let ptr = unsafe { self.storage.unchecked_idx(idx) };
-            // SOUND: we know that the pointer has our schema.
-            Some(unsafe { SchemaRef::from_ptr_schema(ptr, self.schema) })
-        } else {
-            None
-        }
+        self.data.get_idx(idx)
     }
 
     /// Get a mutable reference to the component storage for the given [`Entity`].
@@ -296,7 +527,7 @@ impl UntypedComponentStore {
         entity: Entity,
         f: impl FnOnce() -> T,
     ) -> &mut T {
-        if self.bitset.bit_test(entity.index() as usize) {
+        if self.data.bitset.bit_test(entity.index() as usize) {
             return self.get_mut(entity).unwrap();
         } else {
             self.insert(entity, f());
@@ -312,12 +543,10 @@ impl UntypedComponentStore {
     }
 
     fn get_idx_mut<'a>(&mut self, idx: usize) -> Option<SchemaRefMut<'a>> {
-        if self.bitset.bit_test(idx) {
-            // SOUND: we ensure that there is allocated storage for entities that have their bit
-            // set.
-            let ptr = unsafe { self.storage.unchecked_idx(idx) };
-            // SOUND: we know that the pointer has our schema.
-            Some(unsafe { SchemaRefMut::from_ptr_schema(ptr, self.schema) })
+        // Don't copy shared data if there is nothing to borrow.
+        if self.data.bitset.bit_test(idx) {
+            let tick = self.system_ticks().this_run;
+            self.data_mut().get_idx_mut(idx, tick)
         } else {
             None
         }
@@ -356,7 +585,7 @@ impl UntypedComponentStore {
         &mut self,
         entities: [Entity; N],
     ) -> Result<[Option<&mut T>; N], SchemaMismatchError> {
-        if self.schema != T::schema() {
+        if self.schema() != T::schema() {
             Err(SchemaMismatchError)
         } else {
             let mut refs = self.get_many_ref_mut(entities);
@@ -395,16 +624,19 @@ impl UntypedComponentStore {
             }
         }
 
+        let tick = self.system_ticks().this_run;
+        let store = self.data_mut();
         std::array::from_fn(|i| {
             let index = entities[i].index() as usize;
 
-            if self.bitset.bit_test(index) {
+            if store.bitset.bit_test(index) {
+                store.ticks[index].changed = tick;
                 // SOUND: we've already validated that the contents of storage is valid for type T.
                 // The new lifetime is sound because we validate that all of these borrows don't
                 // overlap and their lifetimes are that of the &mut self borrow.
                 unsafe {
-                    let ptr = self.storage.unchecked_idx(index);
-                    Some(SchemaRefMut::from_ptr_schema(ptr, self.schema))
+                    let ptr = store.storage.unchecked_idx(index);
+                    Some(SchemaRefMut::from_ptr_schema(ptr, store.schema))
                 }
             } else {
                 None
@@ -428,9 +660,9 @@ impl UntypedComponentStore {
         &mut self,
         entity: Entity,
     ) -> Result<Option<T>, SchemaMismatchError> {
-        if self.schema != T::schema() {
+        if self.schema() != T::schema() {
             Err(SchemaMismatchError)
-        } else if self.bitset.contains(entity) {
+        } else if self.data.bitset.contains(entity) {
             let mut data = MaybeUninit::<T>::uninit();
             // SOUND: the data doesn't overlap the storage.
             unsafe { self.remove_raw(entity, Some(data.as_mut_ptr() as *mut c_void)) };
@@ -446,9 +678,9 @@ impl UntypedComponentStore {
 
     /// Remove the component data for the entity if it exists.
     pub fn remove_box(&mut self, entity: Entity) -> Option<SchemaBox> {
-        if self.bitset.contains(entity) {
+        if self.data.bitset.contains(entity) {
             // SOUND: we will immediately initialize the schema box with data matching the schema.
-            let b = unsafe { SchemaBox::uninitialized(self.schema) };
+            let b = unsafe { SchemaBox::uninitialized(self.schema()) };
             // SOUND: the box data doesn't overlap the storage.
             unsafe { self.remove_raw(entity, Some(b.as_ptr())) };
             Some(b)
@@ -468,17 +700,23 @@ impl UntypedComponentStore {
     /// If set, the `out` pointer, must not overlap the internal component storage.
     pub unsafe fn remove_raw(&mut self, entity: Entity, out: Option<*mut c_void>) -> bool {
         let index = entity.index() as usize;
-        let size = self.schema.layout().size();
+        let size = self.schema().layout().size();
 
-        if self.bitset.bit_test(index) {
-            self.bitset.bit_reset(index);
+        // Check before borrowing the data mutably, so that we don't copy shared data if there is
+        // nothing to remove.
+        if self.data.bitset.bit_test(index) {
+            self.record_hook_event(ComponentHookKind::Remove, entity);
+            self.removed.send(entity);
+            self.changes.push(index);
+            let store = self.data_mut();
+            store.bitset.bit_reset(index);
 
-            let ptr = self.storage.unchecked_idx(index);
+            let ptr = store.storage.unchecked_idx(index);
 
             if let Some(out) = out {
                 // SAFE: user asserts `out` is non-overlapping
                 out.copy_from_nonoverlapping(ptr, size);
-            } else if let Some(drop_fn) = &self.schema.drop_fn {
+            } else if let Some(drop_fn) = &store.schema.drop_fn {
                 // SAFE: construcing `UntypedComponentStore` asserts the soundess of the drop_fn
                 //
                 // And ptr is a valid pointer to the component type.
@@ -498,7 +736,7 @@ impl UntypedComponentStore {
     /// Very fast but doesn't allow joining with other component types.
     pub fn iter(&self) -> UntypedComponentStoreIter<'_> {
         UntypedComponentStoreIter {
-            store: self,
+            store: &self.data,
             idx: 0,
         }
     }
@@ -507,8 +745,10 @@ impl UntypedComponentStore {
     ///
     /// Very fast but doesn't allow joining with other component types.
     pub fn iter_mut(&mut self) -> UntypedComponentStoreIterMut<'_> {
+        let tick = self.system_ticks().this_run;
         UntypedComponentStoreIterMut {
-            store: self,
+            store: self.data_mut(),
+            tick,
             idx: 0,
         }
     }
@@ -520,7 +760,7 @@ impl UntypedComponentStore {
     pub fn iter_with_bitset(&self, bitset: Rc<BitSetVec>) -> UntypedComponentBitsetIterator {
         UntypedComponentBitsetIterator {
             current_id: 0,
-            components: self,
+            components: &self.data,
             bitset,
         }
     }
@@ -533,7 +773,7 @@ impl UntypedComponentStore {
     ) -> UntypedComponentOptionalBitsetIterator {
         UntypedComponentOptionalBitsetIterator(UntypedComponentBitsetIterator {
             current_id: 0,
-            components: self,
+            components: &self.data,
             bitset,
         })
     }
@@ -546,9 +786,11 @@ impl UntypedComponentStore {
         &mut self,
         bitset: Rc<BitSetVec>,
     ) -> UntypedComponentBitsetIteratorMut {
+        let tick = self.system_ticks().this_run;
         UntypedComponentBitsetIteratorMut {
             current_id: 0,
-            components: self,
+            components: self.data_mut(),
+            tick,
             bitset,
         }
     }
@@ -559,9 +801,11 @@ impl UntypedComponentStore {
         &mut self,
         bitset: Rc<BitSetVec>,
     ) -> UntypedComponentOptionalBitsetIteratorMut {
+        let tick = self.system_ticks().this_run;
         UntypedComponentOptionalBitsetIteratorMut(UntypedComponentBitsetIteratorMut {
             current_id: 0,
-            components: self,
+            components: self.data_mut(),
+            tick,
             bitset,
         })
     }
@@ -575,7 +819,7 @@ impl UntypedComponentStore {
     /// `iter_mut_with_bitset`. This will iterate over the components of the entity only for
     /// entities that have both components.
     pub fn bitset(&self) -> &BitSetVec {
-        &self.bitset
+        &self.data.bitset
     }
 
     /// Convert into a typed [`ComponentStore`].
@@ -590,7 +834,7 @@ impl UntypedComponentStore {
 
 /// Mutable iterator over pointers in an untyped component store.
 pub struct UntypedComponentStoreIter<'a> {
-    store: &'a UntypedComponentStore,
+    store: &'a ComponentData,
     idx: usize,
 }
 impl<'a> Iterator for UntypedComponentStoreIter<'a> {
@@ -613,7 +857,8 @@ impl<'a> Iterator for UntypedComponentStoreIter<'a> {
 
 /// Mutable iterator over pointers in an untyped component store.
 pub struct UntypedComponentStoreIterMut<'a> {
-    store: &'a mut UntypedComponentStore,
+    store: &'a mut ComponentData,
+    tick: u64,
     idx: usize,
 }
 impl<'a> Iterator for UntypedComponentStoreIterMut<'a> {
@@ -621,7 +866,7 @@ impl<'a> Iterator for UntypedComponentStoreIterMut<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             if self.idx < self.store.max_id {
-                if let Some(ptr) = self.store.get_idx_mut(self.idx) {
+                if let Some(ptr) = self.store.get_idx_mut(self.idx, self.tick) {
                     self.idx += 1;
                     // Re-create the ref to extend the lifetime.
                     // SOUND: We know the pointer will be valid for the lifetime of the store.
@@ -0,0 +1,176 @@
+//! Run conditions for systems and stages.
+//!
+//! A run condition is any system that takes no input and returns a `bool`. Conditions can be
+//! attached to systems with [`ConfigureSystem::run_if()`] or to whole stages with
+//! [`SystemStages::add_stage_run_condition()`].
+
+use crate::prelude::*;
+
+/// Extension trait for combining run conditions.
+pub trait Condition<Args>:
+    IntoSystem<Args, (), bool, Sys = StaticSystem<(), bool>> + Sized
+{
+    /// Create a condition that returns `true` if both this and the `other` condition return
+    /// `true`.
+    ///
+    /// The `other` condition will not be run if this one returns `false`.
+    fn and<OtherArgs, C>(self, other: C) -> StaticSystem<(), bool>
+    where
+        C: IntoSystem<OtherArgs, (), bool, Sys = StaticSystem<(), bool>>,
+    {
+        let mut a = self.system();
+        let mut b = other.system();
+        let mut access = a.access.clone();
+        access.extend(&b.access);
+        StaticSystem {
+            name: a.name,
+            ordering: default(),
+            run_conditions: default(),
+            access,
+            run: Box::new(move |world, ()| a.run(world, ()) && b.run(world, ())),
+        }
+    }
+
+    /// Create a condition that returns `true` if either this or the `other` condition return
+    /// `true`.
+    ///
+    /// The `other` condition will not be run if this one returns `true`.
+    fn or<OtherArgs, C>(self, other: C) -> StaticSystem<(), bool>
+    where
+        C: IntoSystem<OtherArgs, (), bool, Sys = StaticSystem<(), bool>>,
+    {
+        let mut a = self.system();
+        let mut b = other.system();
+        let mut access = a.access.clone();
+        access.extend(&b.access);
+        StaticSystem {
+            name: a.name,
+            ordering: default(),
+            run_conditions: default(),
+            access,
+            run: Box::new(move |world, ()| a.run(world, ()) || b.run(world, ())),
+        }
+    }
+
+    /// Create a condition that returns the opposite of this condition.
+    fn not(self) -> StaticSystem<(), bool> {
+        let mut condition = self.system();
+        StaticSystem {
+            name: condition.name,
+            ordering: default(),
+            run_conditions: default(),
+            access: condition.access.clone(),
+            run: Box::new(move |world, ()| !condition.run(world, ())),
+        }
+    }
+}
+
+impl<Args, S> Condition<Args> for S where S: IntoSystem<Args, (), bool, Sys = StaticSystem<(), bool>>
+{}
+
+/// Run condition that returns `true` if the resource `T` is present in the world.
+pub fn resource_exists<T: HasSchema>() -> StaticSystem<(), bool> {
+    (|resource: Option<Res<T>>| resource.is_some()).system()
+}
+
+/// Run condition that returns `true` if the resource `T` has been changed since the last time
+/// the condition was run.
+///
+/// The first time the condition is run, it returns `true` if the resource exists. It always
+/// returns `false` if the resource does not exist.
+///
+/// See [`UntypedResource::change_count()`] for what counts as a change.
+pub fn resource_changed<T: HasSchema>() -> StaticSystem<(), bool> {
+    let system = SystemId::new_unique();
+    StaticSystem {
+        name: "bones_ecs::condition::resource_changed",
+        ordering: default(),
+        run_conditions: default(),
+        access: {
+            let mut access = SystemAccess::default();
+            access.read_resource(T::schema());
+            access.write_resource(ResourceChangeCursors::schema());
+            access
+        },
+        run: Box::new(move |world, ()| {
+            let resource = world.resources.untyped().get(T::schema());
+            if resource.borrow().is_none() {
+                return false;
+            }
+            let change_count = resource.change_count();
+
+            // The last seen change counts are stored in the world, so that they are restored
+            // along with the resource when loading a snapshot.
+            let cursors = world.resources.get_cell::<ResourceChangeCursors>();
+            let last_seen = cursors
+                .init_borrow_mut(world)
+                .0
+                .insert(system, change_count);
+            last_seen != Some(change_count)
+        }),
+    }
+}
+
+/// Resource storing the last change count seen by each [`resource_changed()`] condition.
+#[derive(HasSchema, Clone, Default)]
+struct ResourceChangeCursors(HashMap<SystemId, u64>);
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Paused;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Score(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Runs(Vec<&'static str>);
+
+    #[test]
+    fn system_run_conditions() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_system_to_stage(
+                Update,
+                (|mut runs: ResMutInit<Runs>| runs.0.push("unpaused"))
+                    .run_if(resource_exists::<Paused>().not()),
+            )
+            .add_system_to_stage(
+                Update,
+                (|mut runs: ResMutInit<Runs>| runs.0.push("score"))
+                    .run_if(resource_changed::<Score>().and(resource_exists::<Paused>().not())),
+            );
+
+        stages.run(&mut world);
+        world.insert_resource(Score(1));
+        stages.run(&mut world);
+        stages.run(&mut world);
+        world.resource_mut::<Score>().0 += 1;
+        world.insert_resource(Paused);
+        stages.run(&mut world);
+
+        assert_eq!(
+            world.resource::<Runs>().0,
+            ["unpaused", "unpaused", "score", "unpaused"]
+        );
+    }
+
+    #[test]
+    fn stage_run_conditions() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_system_to_stage(Update, |mut runs: ResMutInit<Runs>| runs.0.push("update"))
+            .add_system_to_stage(Last, |mut runs: ResMutInit<Runs>| runs.0.push("last"))
+            .add_stage_run_condition(Update, resource_exists::<Paused>().not());
+
+        stages.run(&mut world);
+        world.insert_resource(Paused);
+        stages.run(&mut world);
+
+        assert_eq!(world.resource::<Runs>().0, ["update", "last", "last"]);
+    }
+}
@@ -0,0 +1,572 @@
+//! Structural diffs between two [`World`]s.
+//!
+//! This is useful for debugging desyncs, where two worlds that should be identical are not, or for
+//! checking what a hot reload changed. See [`World::diff()`].
+//!
+//! Values are compared using their [`Schema`]s. If a schema has an `eq_fn`, it is used to skip
+//! equal values quickly, otherwise structs, enums, vecs and maps are compared field by field.
+//! Opaque values that don't have an `eq_fn` can't be compared, so they are listed separately in
+//! [`WorldDiff::uncomparable`], and don't count as differences.
+
+use std::fmt::Write;
+
+use crate::prelude::*;
+
+/// The differences between two [`World`]s, created with [`World::diff()`].
+///
+/// The [`Display`][std::fmt::Display] implementation prints a human-readable report, with one line
+/// per difference.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorldDiff {
+    /// Entities that are alive in the new world, but not in the old one.
+    pub created_entities: Vec<Entity>,
+    /// Entities that are alive in the old world, but not in the new one.
+    pub killed_entities: Vec<Entity>,
+    /// The component differences, sorted by entity.
+    ///
+    /// Components are compared for every entity that is alive in either world.
+    pub components: Vec<ComponentDiff>,
+    /// The resource differences.
+    ///
+    /// The [`Entities`] resource is not included, see
+    /// [`created_entities`][Self::created_entities] and [`killed_entities`][Self::killed_entities]
+    /// instead.
+    pub resources: Vec<ResourceDiff>,
+    /// The values that are present in both worlds, but couldn't be compared, sorted like the
+    /// differences.
+    ///
+    /// These are opaque values that don't have an `eq_fn`. They aren't counted as differences by
+    /// [`is_empty()`][Self::is_empty], because they may well be equal.
+    pub uncomparable: Vec<Uncomparable>,
+}
+
+/// A difference in a component of an entity, part of a [`WorldDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentDiff {
+    /// The entity that the component belongs to.
+    pub entity: Entity,
+    /// The schema of the component.
+    pub schema: &'static Schema,
+    /// How the component differs.
+    pub diff: ValueDiff,
+}
+
+/// A difference in a resource, part of a [`WorldDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceDiff {
+    /// The schema of the resource.
+    pub schema: &'static Schema,
+    /// How the resource differs.
+    pub diff: ValueDiff,
+}
+
+/// A value that couldn't be compared, part of a [`WorldDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Uncomparable {
+    /// The entity that the component belongs to, or [`None`] if the value is in a resource.
+    pub entity: Option<Entity>,
+    /// The schema of the component or resource.
+    pub schema: &'static Schema,
+    /// The path to the value from the root of the component or resource, like
+    /// [`FieldDiff::path`].
+    pub path: String,
+}
+
+/// How a component or resource differs between two worlds.
+///
+/// Values are stored debug-formatted, as returned by [`SchemaRef::debug_format_value()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValueDiff {
+    /// The value is only present in the new world.
+    Added(String),
+    /// The value is only present in the old world.
+    Removed(String),
+    /// The value is present in both worlds, but some of its fields differ.
+    Changed(Vec<FieldDiff>),
+}
+
+/// A field that differs between two values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// The path to the field from the root of the value, such as `.translation.x`, `.items[2]` or
+    /// `.map["key"]`.
+    ///
+    /// The path is empty if the value as a whole differs.
+    pub path: String,
+    /// The debug-formatted value in the old world, or [`None`] if it is an item that is only
+    /// present in the new world.
+    pub old: Option<String>,
+    /// The debug-formatted value in the new world, or [`None`] if it is an item that is only
+    /// present in the old world.
+    pub new: Option<String>,
+}
+
+impl WorldDiff {
+    /// Compare the `old` world with the `new` world.
+    pub fn new(old: &World, new: &World) -> Self {
+        let mut diff = WorldDiff::default();
+        diff.diff_entities(old, new);
+        diff.diff_components(old, new);
+        diff.diff_resources(old, new);
+        diff
+    }
+
+    /// Whether or not the two worlds are the same.
+    ///
+    /// The [uncomparable][Self::uncomparable] values are ignored.
+    pub fn is_empty(&self) -> bool {
+        self.created_entities.is_empty()
+            && self.killed_entities.is_empty()
+            && self.components.is_empty()
+            && self.resources.is_empty()
+    }
+
+    fn diff_entities(&mut self, old: &World, new: &World) {
+        let old_entities = old.resource::<Entities>();
+        let new_entities = new.resource::<Entities>();
+        self.created_entities = alive_entities(&new_entities)
+            .filter(|&e| !old_entities.is_alive(e))
+            .collect();
+        self.killed_entities = alive_entities(&old_entities)
+            .filter(|&e| !new_entities.is_alive(e))
+            .collect();
+    }
+
+    fn diff_components(&mut self, old: &World, new: &World) {
+        let old_entities = old.resource::<Entities>();
+        let new_entities = new.resource::<Entities>();
+        let mut entities = alive_entities(&old_entities)
+            .chain(self.created_entities.iter().copied())
+            .collect::<Vec<_>>();
+        entities.sort();
+
+        let old_stores = old.components.components.read_only_view();
+        let new_stores = new.components.components.read_only_view();
+        let schemas = sorted_schemas(
+            old_stores
+                .values()
+                .chain(new_stores.values())
+                .map(|store| store.borrow().schema()),
+        );
+        let stores = schemas
+            .into_iter()
+            .map(|schema| {
+                let id = schema.id();
+                (
+                    schema,
+                    old_stores.get(&id).map(|x| x.borrow()),
+                    new_stores.get(&id).map(|x| x.borrow()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for entity in entities {
+            for (schema, old_store, new_store) in &stores {
+                let old_value = component(old_store, &old_entities, entity);
+                let new_value = component(new_store, &new_entities, entity);
+                let mut uncomparable = Vec::new();
+                if let Some(diff) =
+                    ValueDiff::with_uncomparable(old_value, new_value, &mut uncomparable)
+                {
+                    self.components.push(ComponentDiff {
+                        entity,
+                        schema,
+                        diff,
+                    });
+                }
+                self.uncomparable
+                    .extend(uncomparable.into_iter().map(|path| Uncomparable {
+                        entity: Some(entity),
+                        schema,
+                        path,
+                    }));
+            }
+        }
+    }
+
+    fn diff_resources(&mut self, old: &World, new: &World) {
+        let old_resources = old.resources.untyped().resources.read_only_view();
+        let new_resources = new.resources.untyped().resources.read_only_view();
+        let schemas = sorted_schemas(
+            old_resources
+                .values()
+                .chain(new_resources.values())
+                .map(|cell| cell.schema())
+                .filter(|&schema| schema != Entities::schema()),
+        );
+
+        for schema in schemas {
+            let id = schema.id();
+            let old_cell = old_resources.get(&id).map(|x| x.borrow());
+            let new_cell = new_resources.get(&id).map(|x| x.borrow());
+            let old_value = old_cell
+                .as_ref()
+                .and_then(|x| x.as_ref())
+                .map(|x| x.as_ref());
+            let new_value = new_cell
+                .as_ref()
+                .and_then(|x| x.as_ref())
+                .map(|x| x.as_ref());
+            let mut uncomparable = Vec::new();
+            if let Some(diff) =
+                ValueDiff::with_uncomparable(old_value, new_value, &mut uncomparable)
+            {
+                self.resources.push(ResourceDiff { schema, diff });
+            }
+            self.uncomparable
+                .extend(uncomparable.into_iter().map(|path| Uncomparable {
+                    entity: None,
+                    schema,
+                    path,
+                }));
+        }
+    }
+}
+
+impl ValueDiff {
+    /// Compare two values with the same schema, returning [`None`] if they are equal or both
+    /// missing.
+    ///
+    /// Opaque fields that don't have an `eq_fn` are skipped, see [`with_uncomparable()`].
+    ///
+    /// [`with_uncomparable()`]: Self::with_uncomparable
+    pub fn new(old: Option<SchemaRef>, new: Option<SchemaRef>) -> Option<Self> {
+        Self::with_uncomparable(old, new, &mut Vec::new())
+    }
+
+    /// Compare two values with the same schema, like [`new()`][Self::new], pushing the paths of
+    /// the fields that couldn't be compared to `uncomparable`.
+    pub fn with_uncomparable(
+        old: Option<SchemaRef>,
+        new: Option<SchemaRef>,
+        uncomparable: &mut Vec<String>,
+    ) -> Option<Self> {
+        match (old, new) {
+            (None, None) => None,
+            (None, Some(new)) => Some(ValueDiff::Added(new.to_string())),
+            (Some(old), None) => Some(ValueDiff::Removed(old.to_string())),
+            (Some(old), Some(new)) => {
+                let mut out = DiffOutput {
+                    fields: Vec::new(),
+                    uncomparable,
+                };
+                diff_fields(&mut String::new(), old, new, &mut out);
+                (!out.fields.is_empty()).then_some(ValueDiff::Changed(out.fields))
+            }
+        }
+    }
+}
+
+/// The output of [`diff_fields()`].
+struct DiffOutput<'a> {
+    /// The fields that differ.
+    fields: Vec<FieldDiff>,
+    /// The paths of the fields that couldn't be compared.
+    uncomparable: &'a mut Vec<String>,
+}
+
+/// Push the fields that differ between `old` and `new` to `out`.
+///
+/// `path` is the path to the values being compared, it is restored before returning.
+fn diff_fields(path: &mut String, old: SchemaRef, new: SchemaRef, out: &mut DiffOutput) {
+    let whole_value = |path: &str| FieldDiff {
+        path: path.to_string(),
+        old: Some(old.to_string()),
+        new: Some(new.to_string()),
+    };
+    // The `eq_fn`s of vecs and maps panic if their items don't have one, so they are always
+    // compared item by item instead.
+    let eq_fn = match old.schema().kind {
+        SchemaKind::Vec(_) | SchemaKind::Map { .. } => None,
+        _ => old.schema().eq_fn.as_ref(),
+    };
+    if let Some(eq_fn) = eq_fn {
+        // SOUND: both pointers have the schema that the `eq_fn` is for.
+        if unsafe { (eq_fn.get())(old.as_ptr(), new.as_ptr()) } {
+            return;
+        }
+    }
+
+    let path_len = path.len();
+    let start = out.fields.len();
+    match (old.access(), new.access()) {
+        (SchemaRefAccess::Struct(old), SchemaRefAccess::Struct(new)) => {
+            diff_struct_fields(path, old, new, out);
+        }
+        (SchemaRefAccess::Enum(old), SchemaRefAccess::Enum(new)) => {
+            if old.variant_idx() != new.variant_idx() {
+                out.fields.push(whole_value(path));
+            } else {
+                diff_struct_fields(path, old.value(), new.value(), out);
+            }
+        }
+        (SchemaRefAccess::Vec(old), SchemaRefAccess::Vec(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                write!(path, "[{i}]").unwrap();
+                match (old.get_ref(i), new.get_ref(i)) {
+                    (Some(old), Some(new)) => diff_fields(path, old, new, out),
+                    (old, new) => out.fields.push(FieldDiff {
+                        path: path.clone(),
+                        old: old.map(|x| x.to_string()),
+                        new: new.map(|x| x.to_string()),
+                    }),
+                }
+                path.truncate(path_len);
+            }
+        }
+        (SchemaRefAccess::Map(old), SchemaRefAccess::Map(new)) => {
+            for (key, old_value) in old.iter() {
+                write!(path, "[{key}]").unwrap();
+                match new.get_ref(key) {
+                    Some(new_value) => diff_fields(path, old_value, new_value, out),
+                    None => out.fields.push(FieldDiff {
+                        path: path.clone(),
+                        old: Some(old_value.to_string()),
+                        new: None,
+                    }),
+                }
+                path.truncate(path_len);
+            }
+            for (key, new_value) in new.iter() {
+                if old.get_ref(key).is_none() {
+                    out.fields.push(FieldDiff {
+                        path: format!("{path}[{key}]"),
+                        old: None,
+                        new: Some(new_value.to_string()),
+                    });
+                }
+            }
+        }
+        (SchemaRefAccess::Primitive(old), SchemaRefAccess::Primitive(new)) => {
+            if matches!(old, PrimitiveRef::Opaque { .. }) && eq_fn.is_none() {
+                out.uncomparable.push(path.clone());
+            } else if !primitive_eq(old, new) {
+                out.fields.push(whole_value(path));
+            }
+        }
+        _ => out.fields.push(whole_value(path)),
+    }
+
+    // If the `eq_fn` says the values differ, make sure that we report something, even if we
+    // couldn't find the difference ourselves.
+    if eq_fn.is_some() && out.fields.len() == start {
+        out.fields.push(whole_value(path));
+    }
+}
+
+fn diff_struct_fields(
+    path: &mut String,
+    old: StructRefAccess,
+    new: StructRefAccess,
+    out: &mut DiffOutput,
+) {
+    let path_len = path.len();
+    for (i, (old, new)) in old.fields().zip(new.fields()).enumerate() {
+        match old.name {
+            Some(name) => write!(path, ".{name}").unwrap(),
+            None => write!(path, ".{i}").unwrap(),
+        }
+        diff_fields(path, old.value, new.value, out);
+        path.truncate(path_len);
+    }
+}
+
+/// Compare two primitives of the same kind.
+///
+/// Floats are compared bitwise, so that `NaN`s are equal to themselves and `0.0` differs from
+/// `-0.0`, which is what matters when looking for desyncs.
+fn primitive_eq(old: PrimitiveRef, new: PrimitiveRef) -> bool {
+    match (old, new) {
+        (PrimitiveRef::Bool(a), PrimitiveRef::Bool(b)) => a == b,
+        (PrimitiveRef::U8(a), PrimitiveRef::U8(b)) => a == b,
+        (PrimitiveRef::U16(a), PrimitiveRef::U16(b)) => a == b,
+        (PrimitiveRef::U32(a), PrimitiveRef::U32(b)) => a == b,
+        (PrimitiveRef::U64(a), PrimitiveRef::U64(b)) => a == b,
+        (PrimitiveRef::U128(a), PrimitiveRef::U128(b)) => a == b,
+        (PrimitiveRef::I8(a), PrimitiveRef::I8(b)) => a == b,
+        (PrimitiveRef::I16(a), PrimitiveRef::I16(b)) => a == b,
+        (PrimitiveRef::I32(a), PrimitiveRef::I32(b)) => a == b,
+        (PrimitiveRef::I64(a), PrimitiveRef::I64(b)) => a == b,
+        (PrimitiveRef::I128(a), PrimitiveRef::I128(b)) => a == b,
+        (PrimitiveRef::F32(a), PrimitiveRef::F32(b)) => a.to_bits() == b.to_bits(),
+        (PrimitiveRef::F64(a), PrimitiveRef::F64(b)) => a.to_bits() == b.to_bits(),
+        (PrimitiveRef::String(a), PrimitiveRef::String(b)) => a == b,
+        // Opaque values can only be compared with an `eq_fn`, which has already said that they
+        // differ.
+        _ => false,
+    }
+}
+
+/// Get the component of the entity, if it is alive and has one.
+fn component<'a>(
+    store: &'a Option<Ref<UntypedComponentStore>>,
+    entities: &Entities,
+    entity: Entity,
+) -> Option<SchemaRef<'a>> {
+    if entities.is_alive(entity) {
+        store.as_ref()?.get_ref(entity)
+    } else {
+        None
+    }
+}
+
+/// Iterate over the alive entities.
+fn alive_entities(entities: &Entities) -> impl Iterator<Item = Entity> + '_ {
+    entities.iter_with_bitset(entities.bitset())
+}
+
+/// Deduplicate the schemas, sorting them by name so that the diff is deterministic.
+pub(crate) fn sorted_schemas(
+    schemas: impl Iterator<Item = &'static Schema>,
+) -> Vec<&'static Schema> {
+    let schemas = schemas.map(|x| (x.id(), x)).collect::<HashMap<_, _>>();
+    let mut schemas = schemas.into_values().collect::<Vec<_>>();
+    schemas.sort_by_key(|x| x.full_name);
+    schemas
+}
+
+impl std::fmt::Display for WorldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            writeln!(f, "Worlds are identical")?;
+        }
+        for entity in &self.created_entities {
+            writeln!(f, "{entity:?}: created")?;
+        }
+        for entity in &self.killed_entities {
+            writeln!(f, "{entity:?}: killed")?;
+        }
+        for component in &self.components {
+            let prefix = format!("{:?} {}", component.entity, component.schema.name);
+            write_value_diff(f, &prefix, &component.diff)?;
+        }
+        for resource in &self.resources {
+            let prefix = format!("Resource {}", resource.schema.name);
+            write_value_diff(f, &prefix, &resource.diff)?;
+        }
+        for value in &self.uncomparable {
+            match value.entity {
+                Some(entity) => write!(f, "{entity:?} {}", value.schema.name)?,
+                None => write!(f, "Resource {}", value.schema.name)?,
+            }
+            writeln!(f, "{}: not comparable", value.path)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_value_diff(
+    f: &mut std::fmt::Formatter<'_>,
+    prefix: &str,
+    diff: &ValueDiff,
+) -> std::fmt::Result {
+    match diff {
+        ValueDiff::Added(value) => writeln!(f, "{prefix}: added {value}"),
+        ValueDiff::Removed(value) => writeln!(f, "{prefix}: removed {value}"),
+        ValueDiff::Changed(fields) => {
+            for field in fields {
+                let old = field.old.as_deref().unwrap_or("<none>");
+                let new = field.new.as_deref().unwrap_or("<none>");
+                writeln!(f, "{prefix}{}: {old} -> {new}", field.path)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default, Debug)]
+    #[repr(C)]
+    struct Pos {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(HasSchema, Clone, Default, Debug)]
+    #[repr(C)]
+    struct Inventory {
+        items: SVec<u32>,
+        // The items of this vec don't have an `eq_fn`.
+        positions: SVec<Pos>,
+    }
+
+    #[derive(HasSchema, Clone, Default, Debug)]
+    #[repr(C)]
+    struct Score(u32);
+
+    #[test]
+    fn world_diff() {
+        let mut old = World::new();
+        old.insert_resource(Score(1));
+        let [a, b] = old.run_system(
+            |mut entities: ResMut<Entities>,
+             mut pos: CompMut<Pos>,
+             mut inventories: CompMut<Inventory>| {
+                let [a, b] = std::array::from_fn(|_| entities.create());
+                pos.insert(a, Pos { x: 1.0, y: 2.0 });
+                pos.insert(b, Pos::default());
+                inventories.insert(a, Inventory::default());
+                [a, b]
+            },
+            (),
+        );
+        assert!(old.diff(&old.clone()).is_empty());
+
+        let new = old.clone();
+        new.resource_mut::<Score>().0 = 2;
+        let c = new.run_system(
+            move |mut entities: ResMut<Entities>,
+                  mut pos: CompMut<Pos>,
+                  mut inventories: CompMut<Inventory>| {
+                entities.kill(b);
+                pos.get_mut(a).unwrap().y = 3.0;
+                let inventory = inventories.get_mut(a).unwrap();
+                inventory.items.push(7);
+                inventory.positions.push(Pos { x: 1.0, y: 0.0 });
+                let c = entities.create();
+                inventories.insert(c, Inventory::default());
+                c
+            },
+            (),
+        );
+        new.maintain();
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.created_entities, [c]);
+        assert_eq!(diff.killed_entities, [b]);
+        assert_eq!(
+            diff.to_string(),
+            format!(
+                "{c:?}: created\n\
+                 {b:?}: killed\n\
+                 {a:?} Inventory.items[0]: <none> -> 7\n\
+                 {a:?} Inventory.positions[0]: <none> -> Pos {{ x: 1, y: 0 }}\n\
+                 {a:?} Pos.y: 2 -> 3\n\
+                 {b:?} Pos: removed Pos {{ x: 0, y: 0 }}\n\
+                 {c:?} Inventory: added Inventory {{ items: [], positions: [] }}\n\
+                 Resource Score.0: 1 -> 2\n"
+            )
+        );
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Opaque;
+
+    #[test]
+    fn opaque_values_are_not_comparable() {
+        let mut world = World::new();
+        world.init_resource::<Opaque>();
+
+        // Identical worlds are reported as such, even though the opaque values can't be compared.
+        let diff = world.diff(&world.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.uncomparable.len(), 1);
+        assert_eq!(
+            diff.to_string(),
+            "Worlds are identical\nResource Opaque: not comparable\n"
+        );
+    }
+}
@@ -1,9 +1,20 @@
 //! [`Entity`] implementation, storage, and interation.
 
-use std::{marker::PhantomData, rc::Rc};
+use std::{
+    marker::PhantomData,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bevy_tasks::{ComputeTaskPool, TaskPool};
 
 use crate::prelude::*;
 
+/// The maximum number of concurrent entities.
+///
+/// The last index is reserved for [`Entity::INVALID`].
+const MAX_ENTITIES: usize = u32::MAX as usize;
+
 /// An entity index.
 ///
 /// They are created using the `Entities` struct. They are used as indices with `Components`
@@ -56,7 +67,7 @@ impl Default for Entity {
 /// It also holds a list of entities that were recently killed, which allows to remove components of
 /// deleted entities at the end of a game frame.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Clone, HasSchema)]
+#[derive(HasSchema)]
 pub struct Entities {
     /// Bitset containing all living entities
     alive: BitSetVec,
@@ -66,6 +77,25 @@ pub struct Entities {
     /// helps to know if we should directly append after next_id or if we should look through the
     /// bitset.
     has_deleted: bool,
+    /// The number of entities that have been reserved with [`Entities::reserve()`] since the last
+    /// flush, starting at `next_id`.
+    reserved: AtomicUsize,
+    /// The indices of the entities that were created or killed, used to update [`QueryCache`]s.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) changes: crate::query::ChangeLog,
+}
+impl Clone for Entities {
+    fn clone(&self) -> Self {
+        Self {
+            alive: self.alive.clone(),
+            generation: self.generation.clone(),
+            killed: self.killed.clone(),
+            next_id: self.next_id,
+            has_deleted: self.has_deleted,
+            reserved: AtomicUsize::new(self.reserved.load(Ordering::Relaxed)),
+            changes: self.changes.clone(),
+        }
+    }
 }
 impl std::fmt::Debug for Entities {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -77,14 +107,26 @@ impl Default for Entities {
     fn default() -> Self {
         Self {
             alive: create_bitset(),
-            generation: vec![0u32; BITSET_SIZE],
+            generation: Vec::new(),
             killed: vec![],
             next_id: 0,
             has_deleted: false,
+            reserved: AtomicUsize::new(0),
+            changes: default(),
         }
     }
 }
 
+/// A marker for the [`SystemAccess`] of systems that reserve entities, like systems with
+/// [`Commands`].
+///
+/// Reserving entities only needs shared access to the [`Entities`], so systems that reserve
+/// entities write to this marker instead. That way they never run in parallel with each other, so
+/// the reserved IDs are deterministic, but they may run in parallel with systems that only read
+/// the [`Entities`].
+#[derive(HasSchema, Clone, Default)]
+pub struct EntityReservations;
+
 /// A type representing a component-joining entity query.
 pub trait QueryItem {
     /// The type of iterator this query item creates
@@ -93,6 +135,14 @@ pub trait QueryItem {
     fn apply_bitset(&self, bitset: &mut BitSetVec);
     /// Return an iterator over the provided bitset.
     fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter;
+    /// Add the component stores that [`apply_bitset()`][Self::apply_bitset] filters the entities
+    /// with to the filter of a [`QueryCache`].
+    ///
+    /// The default implementation marks the filter as uncached, so that `apply_bitset()` is called
+    /// every time that a cached query is run.
+    fn cache_filter<'s>(&'s self, filter: &mut QueryFilter<'s>) {
+        filter.uncached();
+    }
 }
 
 /// Wrapper for the [`Comp`] [`SystemParam`] used as [`QueryItem`] to iterate
@@ -151,6 +201,103 @@ where
     OptionalQueryItemMut(component_ref, PhantomData)
 }
 
+/// Wrapper for the [`Comp`] or [`CompMut`] [`SystemParam`] used as [`QueryItem`] to iterate over
+/// entities whose component was added since the system last ran.
+///
+/// See [`Added`] helper func for constructing `AddedQueryItem` and usage.
+pub struct AddedQueryItem<'a, T: HasSchema, S>(pub &'a S, pub PhantomData<&'a T>);
+
+/// Wrapper for the [`Comp`] or [`CompMut`] [`SystemParam`] used as [`QueryItem`] to iterate over
+/// entities whose component was inserted or mutably borrowed since the system last ran.
+///
+/// See [`Changed`] helper func for constructing `ChangedQueryItem` and usage.
+pub struct ChangedQueryItem<'a, T: HasSchema, S>(pub &'a S, pub PhantomData<&'a T>);
+
+/// Helper func to construct an [`AddedQueryItem`] wrapping a [`Comp`] or [`CompMut`]
+/// [`SystemParam`]. Used to iterate over entities that had the component added since the system
+/// last ran.
+///
+/// This example iterates over the positions of entities that just got a `Health` component.
+///
+/// `entities.iter_with((&pos, Added(&health)))`
+///
+/// See [`ComponentStore::is_added()`] for more details on how ticks are counted.
+#[allow(non_snake_case)]
+pub fn Added<'a, T: HasSchema, C, S>(component_ref: &'a S) -> AddedQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    AddedQueryItem(component_ref, PhantomData)
+}
+
+/// Helper func to construct a [`ChangedQueryItem`] wrapping a [`Comp`] or [`CompMut`]
+/// [`SystemParam`]. Used to iterate over entities that had the component inserted or mutably
+/// borrowed since the system last ran.
+///
+/// This example iterates over the entities whose transform changed.
+///
+/// `entities.iter_with(Changed(&transforms))`
+///
+/// See [`ComponentStore::is_changed()`] for more details on how ticks are counted.
+#[allow(non_snake_case)]
+pub fn Changed<'a, T: HasSchema, C, S>(component_ref: &'a S) -> ChangedQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    ChangedQueryItem(component_ref, PhantomData)
+}
+
+/// Wrapper for the [`Comp`] or [`CompMut`] [`SystemParam`] used as [`QueryItem`] to only iterate
+/// over entities that have the component, without retrieving it.
+///
+/// See [`With`] helper func for constructing `WithQueryItem` and usage.
+pub struct WithQueryItem<'a, T: HasSchema, S>(pub &'a S, pub PhantomData<&'a T>);
+
+/// Wrapper for the [`Comp`] or [`CompMut`] [`SystemParam`] used as [`QueryItem`] to only iterate
+/// over entities that don't have the component.
+///
+/// See [`Without`] helper func for constructing `WithoutQueryItem` and usage.
+pub struct WithoutQueryItem<'a, T: HasSchema, S>(pub &'a S, pub PhantomData<&'a T>);
+
+/// Helper func to construct a [`WithQueryItem`] wrapping a [`Comp`] or [`CompMut`]
+/// [`SystemParam`]. Used to filter entities by a component without retrieving it.
+///
+/// This only modifies the bitset used to iterate over the entities, and yields `()` for each
+/// entity.
+///
+/// This example iterates over the positions of entities that have a `Player` component.
+///
+/// `entities.iter_with((&mut pos, With(&players)))`
+#[allow(non_snake_case)]
+pub fn With<'a, T: HasSchema, C, S>(component_ref: &'a S) -> WithQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    WithQueryItem(component_ref, PhantomData)
+}
+
+/// Helper func to construct a [`WithoutQueryItem`] wrapping a [`Comp`] or [`CompMut`]
+/// [`SystemParam`]. Used to skip entities that have the component.
+///
+/// This only modifies the bitset used to iterate over the entities, and yields `()` for each
+/// entity.
+///
+/// This example iterates over the positions of entities that don't have a `Frozen` component,
+/// optionally retrieving their velocity.
+///
+/// `entities.iter_with((&mut pos, &Optional(&vel), Without(&frozen)))`
+#[allow(non_snake_case)]
+pub fn Without<'a, T: HasSchema, C, S>(component_ref: &'a S) -> WithoutQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    WithoutQueryItem(component_ref, PhantomData)
+}
+
 impl<'a> QueryItem for &'a Ref<'a, UntypedComponentStore> {
     type Iter = UntypedComponentBitsetIterator<'a>;
     fn apply_bitset(&self, bitset: &mut BitSetVec) {
@@ -160,6 +307,10 @@ impl<'a> QueryItem for &'a Ref<'a, UntypedComponentStore> {
     fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
         UntypedComponentStore::iter_with_bitset(self, bitset)
     }
+
+    fn cache_filter<'s>(&'s self, filter: &mut QueryFilter<'s>) {
+        filter.with(self);
+    }
 }
 
 impl<'a, 'q, T: HasSchema> QueryItem for &'a Comp<'q, T> {
@@ -171,6 +322,10 @@ impl<'a, 'q, T: HasSchema> QueryItem for &'a Comp<'q, T> {
     fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
         ComponentStore::iter_with_bitset(&**self, bitset)
     }
+
+    fn cache_filter<'s>(&'s self, filter: &mut QueryFilter<'s>) {
+        filter.with(self.as_untyped());
+    }
 }
 
 impl<'a, 'q, T: HasSchema> QueryItem for &'a CompMut<'q, T> {
@@ -182,6 +337,10 @@ impl<'a, 'q, T: HasSchema> QueryItem for &'a CompMut<'q, T> {
     fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
         ComponentStore::iter_with_bitset(&**self, bitset)
     }
+
+    fn cache_filter<'s>(&'s self, filter: &mut QueryFilter<'s>) {
+        filter.with(self.as_untyped());
+    }
 }
 
 impl<'a, 'q, T: HasSchema> QueryItem for &'a mut CompMut<'q, T> {
@@ -193,6 +352,10 @@ impl<'a, 'q, T: HasSchema> QueryItem for &'a mut CompMut<'q, T> {
     fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
         ComponentStore::iter_mut_with_bitset(self, bitset)
     }
+
+    fn cache_filter<'s>(&'s self, filter: &mut QueryFilter<'s>) {
+        filter.with(self.as_untyped());
+    }
 }
 
 /// Immutably iterate over optional component with syntax: `&Optional(&Comp<T>)` / `&Optional(&CompMut<T>)`.
@@ -208,6 +371,8 @@ where
     fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
         self.0.iter_with_bitset_optional(bitset)
     }
+
+    fn cache_filter<'s>(&'s self, _filter: &mut QueryFilter<'s>) {}
 }
 
 /// Mutably iterate over optional component with syntax: `&mut OptionalMut(&mut RefMut<ComponentStore<T>>)`
@@ -222,6 +387,82 @@ where
     fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
         self.0.iter_mut_with_bitset_optional(bitset)
     }
+
+    fn cache_filter<'s>(&'s self, _filter: &mut QueryFilter<'s>) {}
+}
+
+/// Immutably iterate over recently added components with syntax: `Added(&Comp<T>)` /
+/// `Added(&CompMut<T>)`.
+impl<'a, T: HasSchema, S, C> QueryItem for AddedQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type Iter = ComponentBitsetIterator<'a, T>;
+    fn apply_bitset(&self, bitset: &mut BitSetVec) {
+        self.0.component_store().as_untyped().retain_added(bitset);
+    }
+
+    fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
+        self.0.iter_with_bitset(bitset)
+    }
+}
+
+/// Immutably iterate over recently changed components with syntax: `Changed(&Comp<T>)` /
+/// `Changed(&CompMut<T>)`.
+impl<'a, T: HasSchema, S, C> QueryItem for ChangedQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type Iter = ComponentBitsetIterator<'a, T>;
+    fn apply_bitset(&self, bitset: &mut BitSetVec) {
+        self.0.component_store().as_untyped().retain_changed(bitset);
+    }
+
+    fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
+        self.0.iter_with_bitset(bitset)
+    }
+}
+
+/// Filter entities by a component with syntax: `With(&Comp<T>)` / `With(&CompMut<T>)`.
+impl<'a, T: HasSchema, S, C> QueryItem for WithQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type Iter = std::iter::Repeat<()>;
+    fn apply_bitset(&self, bitset: &mut BitSetVec) {
+        bitset.bit_and(self.0.bitset());
+    }
+
+    fn iter_with_bitset(self, _bitset: Rc<BitSetVec>) -> Self::Iter {
+        std::iter::repeat(())
+    }
+
+    fn cache_filter<'s>(&'s self, filter: &mut QueryFilter<'s>) {
+        filter.with(self.0.component_store().as_untyped());
+    }
+}
+
+/// Filter out entities by a component with syntax: `Without(&Comp<T>)` / `Without(&CompMut<T>)`.
+impl<'a, T: HasSchema, S, C> QueryItem for WithoutQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type Iter = std::iter::Repeat<()>;
+    fn apply_bitset(&self, bitset: &mut BitSetVec) {
+        bitset.bit_andnot(self.0.bitset());
+    }
+
+    fn iter_with_bitset(self, _bitset: Rc<BitSetVec>) -> Self::Iter {
+        std::iter::repeat(())
+    }
+
+    fn cache_filter<'s>(&'s self, filter: &mut QueryFilter<'s>) {
+        filter.without(self.0.component_store().as_untyped());
+    }
 }
 
 #[doc(hidden)]
@@ -298,6 +539,18 @@ macro_rules! impl_query {
                 )*
             }
 
+            #[allow(non_snake_case)]
+            fn cache_filter<'s>(&'s self, filter: &mut QueryFilter<'s>) {
+                let (
+                    $(
+                        $args,
+                    )*
+                ) = self;
+                $(
+                    $args.cache_filter(filter);
+                )*
+            }
+
             #[allow(non_snake_case)]
             fn iter_with_bitset(self, bitset: Rc<BitSetVec>) -> Self::Iter {
                 let (
@@ -347,9 +600,11 @@ impl<'a, I: Iterator> Iterator for EntitiesIterWith<'a, I> {
     type Item = (Entity, I::Item);
 
     fn next(&mut self) -> Option<Self::Item> {
-        while !self.bitset.bit_test(self.current_id) && self.current_id < self.next_id {
-            self.current_id += 1;
-        }
+        self.current_id = self
+            .bitset
+            .next_set_bit(self.current_id)
+            .unwrap_or(self.next_id)
+            .min(self.next_id);
 
         if self.current_id >= self.next_id {
             return None;
@@ -441,36 +696,115 @@ impl Entities {
         }
     }
 
+    /// Iterate over the entities and components in the given query, like
+    /// [`iter_with()`][Self::iter_with], using a [`QueryCache`] to avoid joining the bitsets of
+    /// the components every time.
+    ///
+    /// The cache should always be used with the same query, usually by storing it in a
+    /// [`Local`] system parameter. See the [`query`][crate::query] module.
+    pub fn iter_with_cache<Q: QueryItem>(
+        &self,
+        cache: &mut QueryCache,
+        query: Q,
+    ) -> EntitiesIterWith<<Q as QueryItem>::Iter> {
+        let mut filter = QueryFilter::default();
+        query.cache_filter(&mut filter);
+        cache.update(self, &filter);
+        let uncached = filter.is_uncached();
+
+        let mut bitset = cache.bitset().clone();
+        if uncached {
+            query.apply_bitset(&mut bitset);
+        }
+        let bitset = Rc::new(bitset);
+
+        EntitiesIterWith {
+            current_id: 0,
+            next_id: self.next_id,
+            bitset: bitset.clone(),
+            generations: &self.generation,
+            query: query.iter_with_bitset(bitset),
+        }
+    }
+
+    /// Run a closure for the entities and components in the given query in parallel, on the
+    /// [`ComputeTaskPool`].
+    ///
+    /// The values returned by the closure are collected in entity order. See the
+    /// [`parallel`][crate::parallel] module.
+    pub fn par_iter_with<Q, F, R>(&self, query: Q, f: F) -> Vec<R>
+    where
+        Q: ParQueryItem,
+        F: Fn(Entity, Q::ParItem) -> R + Send + Sync,
+        R: Send + 'static,
+    {
+        let mut bitset = self.bitset().clone();
+        query.apply_bitset(&mut bitset);
+        let fetch = query.fetch();
+
+        let (bitset, fetch, f, generations) = (&bitset, &fetch, &f, &self.generation);
+        let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+        let batches = task_pool.scope(|scope| {
+            for start in (0..bitset.bit_len()).step_by(PAR_BATCH_SIZE) {
+                let end = start + PAR_BATCH_SIZE;
+                if !bitset.next_set_bit(start).is_some_and(|index| index < end) {
+                    continue;
+                }
+                scope.spawn(async move {
+                    let mut results = Vec::new();
+                    let mut next = start;
+                    while let Some(index) = bitset.next_set_bit(next).filter(|&index| index < end) {
+                        next = index + 1;
+                        let entity = Entity::new(index as u32, generations[index]);
+                        // SAFE: the index is in the filtered bitset, and the batches don't
+                        // overlap, so it is only visited once.
+                        let item = unsafe { Q::get(fetch, index) };
+                        results.push(f(entity, item));
+                    }
+                    results
+                });
+            }
+        });
+        batches.into_iter().flatten().collect()
+    }
+
     /// Creates a new `Entity` and returns it.
     ///
     /// This function will not reuse the index of an entity that is still in the killed entities.
+    ///
+    /// Any entities that were [reserved][Commands::spawn] are [flushed][Self::flush] first.
     pub fn create(&mut self) -> Entity {
+        self.flush();
         if !self.has_deleted {
             let i = self.next_id;
-            if i >= BITSET_SIZE {
+            if i >= MAX_ENTITIES {
                 panic!("Exceeded maximum amount of concurrent entities.");
             }
             self.next_id += 1;
+            self.generation.resize(self.next_id, 0);
             self.alive.bit_set(i);
+            self.changes.push(i);
             Entity::new(i as u32, self.generation[i])
         } else {
             let mut section = 0;
             // Find section where at least one bit isn't set
-            while self.alive[section].bit_all() {
+            while self.alive.get(section).is_some_and(|x| x.bit_all()) {
                 section += 1;
             }
             let mut i = section * (32 * 8);
             while self.alive.bit_test(i) || self.killed.iter().any(|e| e.index() == i as u32) {
                 i += 1;
             }
+            if i >= MAX_ENTITIES {
+                panic!("Exceeded maximum amount of concurrent entities.");
+            }
             self.alive.bit_set(i);
+            self.changes.push(i);
             if i >= self.next_id {
                 self.next_id = i + 1;
+                self.generation.resize(self.next_id, 0);
                 self.has_deleted = false;
             }
-            if i >= BITSET_SIZE {
-                panic!("Exceeded maximum amount of concurrent entities.");
-            }
             let entity = Entity::new(i as u32, self.generation[i]);
 
             // Make sure we never return the invalid entity.
@@ -482,24 +816,70 @@ impl Entities {
         }
     }
 
+    /// Reserve an entity without requiring mutable access to the [`Entities`].
+    ///
+    /// The entity ID can be used right away, but the entity only becomes alive when the reserved
+    /// entities are [flushed][Self::flush]. This happens in [`World::maintain()`], before the
+    /// [`Commands`] of a stage are applied, and before an entity is [created][Self::create].
+    ///
+    /// Reserved entities always get new indices, in the order that they were reserved, so the
+    /// allocated IDs are deterministic as long as the systems that reserve entities run in a
+    /// deterministic order. This is why systems reserve entities with [`Commands::spawn()`], which
+    /// declares the [`EntityReservations`] access, rather than through [`Res<Entities>`][Res].
+    pub(crate) fn reserve(&self) -> Entity {
+        let i = self.next_id + self.reserved.fetch_add(1, Ordering::Relaxed);
+        if i >= MAX_ENTITIES {
+            panic!("Exceeded maximum amount of concurrent entities.");
+        }
+        // Indices past `next_id` haven't been used yet, so their generation is zero.
+        Entity::new(i as u32, self.generation.get(i).copied().unwrap_or(0))
+    }
+
+    /// Make the entities [reserved][Commands::spawn] since the last flush alive.
+    pub fn flush(&mut self) {
+        let reserved = std::mem::take(self.reserved.get_mut());
+        for i in self.next_id..self.next_id + reserved {
+            self.alive.bit_set(i);
+            self.changes.push(i);
+        }
+        self.next_id += reserved;
+        self.generation.resize(self.next_id, 0);
+    }
+
     /// Checks if the `Entity` is still alive.
     ///
     /// Returns true if it is alive. Returns false if it has been killed.
     pub fn is_alive(&self, entity: Entity) -> bool {
         self.alive.bit_test(entity.index() as usize)
-            && self.generation[entity.index() as usize] == entity.generation()
+            && self.generation.get(entity.index() as usize) == Some(&entity.generation())
     }
 
     /// Kill an entity.
     pub fn kill(&mut self, entity: Entity) {
         if self.alive.bit_test(entity.index() as usize) {
             self.alive.bit_reset(entity.index() as usize);
+            self.changes.push(entity.index() as usize);
             self.generation[entity.index() as usize] += 1;
             self.killed.push(entity);
             self.has_deleted = true;
         }
     }
 
+    /// Kill an entity along with all of its descendants in the entity hierarchy.
+    ///
+    /// See the [`hierarchy`][crate::hierarchy] module.
+    pub fn kill_recursive(&mut self, entity: Entity, children: &ComponentStore<Children>) {
+        if !self.is_alive(entity) {
+            return;
+        }
+        self.kill(entity);
+        if let Some(entity_children) = children.get(entity) {
+            for &child in entity_children.iter() {
+                self.kill_recursive(child, children);
+            }
+        }
+    }
+
     /// Returns entities in the killed list.
     pub fn killed(&self) -> &Vec<Entity> {
         &self.killed
@@ -510,6 +890,13 @@ impl Entities {
         self.killed.clear();
     }
 
+    /// Remove the unused memory at the end of the alive entity bitset.
+    ///
+    /// This is called in [`World::maintain()`].
+    pub fn shrink(&mut self) {
+        self.alive.shrink();
+    }
+
     /// Returns a bitset where each index where the bit is set to 1 indicates the index of an alive
     /// entity.
     ///
@@ -528,6 +915,44 @@ impl Entities {
             bitset,
         }
     }
+
+    /// Get the generation of every entity index that has been used so far.
+    ///
+    /// For alive entities this is their current generation, and for dead ones it is the generation
+    /// that the next entity created at that index will have.
+    #[cfg(feature = "serde")]
+    pub(crate) fn generations(&self) -> &[u32] {
+        &self.generation[..self.next_id]
+    }
+
+    /// Create the entities from the generation of every used entity index, as returned by
+    /// [`generations()`][Self::generations], and the entities that are alive.
+    ///
+    /// Returns [`None`] if an alive entity doesn't match the generations.
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_generations(
+        generations: &[u32],
+        alive: impl IntoIterator<Item = Entity>,
+    ) -> Option<Self> {
+        let mut entities = Entities {
+            generation: generations.to_vec(),
+            next_id: generations.len(),
+            ..Default::default()
+        };
+        let mut alive_count = 0;
+        for entity in alive {
+            let index = entity.index() as usize;
+            if generations.get(index) != Some(&entity.generation())
+                || entities.alive.bit_test(index)
+            {
+                return None;
+            }
+            entities.alive.bit_set(index);
+            alive_count += 1;
+        }
+        entities.has_deleted = alive_count < entities.next_id;
+        Some(entities)
+    }
 }
 
 /// Iterator over entities using the provided bitset.
@@ -543,21 +968,17 @@ pub struct EntityIterator<'a> {
 impl<'a> Iterator for EntityIterator<'a> {
     type Item = Entity;
     fn next(&mut self) -> Option<Self::Item> {
-        while !(self.bitset.bit_test(self.current_id) && self.entities.bit_test(self.current_id))
-            && self.current_id < self.next_id
-        {
-            self.current_id += 1;
+        while let Some(id) = self.bitset.next_set_bit(self.current_id) {
+            if id >= self.next_id {
+                break;
+            }
+            self.current_id = id + 1;
+            if self.entities.bit_test(id) {
+                return Some(Entity::new(id as u32, self.generations[id]));
+            }
         }
-        let ret = if self.current_id < self.next_id {
-            Some(Entity::new(
-                self.current_id as u32,
-                self.generations[self.current_id],
-            ))
-        } else {
-            None
-        };
-        self.current_id += 1;
-        ret
+        self.current_id = self.next_id;
+        None
     }
 }
 
@@ -595,6 +1016,24 @@ mod tests {
         assert_eq!(*entities.killed(), vec![]);
     }
 
+    #[test]
+    fn reserve_entities() {
+        let mut entities = Entities::default();
+        let e1 = entities.create();
+        entities.kill(e1);
+        entities.clear_killed();
+
+        // Reserved entities get new indices in order, and are alive once flushed.
+        let shared = &entities;
+        let [r1, r2] = [shared.reserve(), shared.reserve()];
+        assert_eq!((r1.index(), r2.index()), (1, 2));
+        assert!(!entities.is_alive(r1));
+        let e2 = entities.create();
+        assert!(entities.is_alive(r1) && entities.is_alive(r2));
+        assert_eq!(e2.index(), 0);
+        assert_eq!(entities.reserve().index(), 3);
+    }
+
     #[test]
     fn test_interleaved_create_kill() {
         let mut entities = Entities::default();
@@ -653,27 +1092,150 @@ mod tests {
 
     #[cfg(not(miri))] // This test is very slow on miri and not critical to test for.
     #[test]
-    #[should_panic(expected = "Exceeded maximum amount")]
-    fn force_max_entity_panic() {
-        let mut entities = Entities::default();
-        for _ in 0..(BITSET_SIZE + 1) {
-            entities.create();
-        }
-    }
-
-    #[cfg(not(miri))] // This test is very slow on miri and not critical to test for.
-    #[test]
-    #[should_panic(expected = "Exceeded maximum amount")]
-    fn force_max_entity_panic2() {
+    fn grow_past_65536_entities() {
         let mut entities = Entities::default();
         let mut e = None;
-        for _ in 0..BITSET_SIZE {
+        for _ in 0..70_000 {
             e = Some(entities.create());
         }
         let e = e.unwrap();
+        assert_eq!(e.index(), 69_999);
         entities.kill(e);
-        entities.create();
-        entities.create();
+        entities.clear_killed();
+        let e2 = entities.create();
+        assert_eq!((e2.index(), e2.generation()), (69_999, 1));
+        assert!(!entities.is_alive(e) && entities.is_alive(e2));
+        assert_eq!(entities.create().index(), 70_000);
+    }
+
+    #[test]
+    fn iter_with_added_changed() {
+        #[derive(HasSchema, Clone, Default)]
+        #[repr(C)]
+        struct A(u32);
+
+        let world = World::new();
+        let (e1, e2) = world.run_system(
+            |mut entities: ResMut<Entities>, mut comps: CompMut<A>| {
+                let e1 = entities.create();
+                let e2 = entities.create();
+                comps.insert(e1, A(1));
+                comps.insert(e2, A(2));
+                (e1, e2)
+            },
+            (),
+        );
+
+        let mut added = (|entities: Res<Entities>, comps: Comp<A>| {
+            entities
+                .iter_with(Added(&comps))
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>()
+        })
+        .system();
+        let mut changed = (|entities: Res<Entities>, comps: Comp<A>| {
+            entities
+                .iter_with(Changed(&comps))
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>()
+        })
+        .system();
+
+        // Every system sees the additions once, no matter how many frames passed.
+        assert_eq!(added.run(&world, ()), vec![e1, e2]);
+        assert_eq!(added.run(&world, ()), vec![]);
+        world.maintain();
+        world.maintain();
+        assert_eq!(changed.run(&world, ()), vec![e1, e2]);
+        assert_eq!(changed.run(&world, ()), vec![]);
+
+        // Mutably borrowing a component marks it as changed, but not added.
+        let mut mutate = (move |mut comps: CompMut<A>| {
+            comps.get_mut(e2).unwrap().0 += 1;
+        })
+        .system();
+        mutate.run(&world, ());
+        assert_eq!(added.run(&world, ()), vec![]);
+        assert_eq!(changed.run(&world, ()), vec![e2]);
+        assert_eq!(changed.run(&world, ()), vec![]);
+
+        // A system doesn't see its own changes the next time it runs.
+        let mut mutate_changed = (move |entities: Res<Entities>, mut comps: CompMut<A>| {
+            let changed = entities
+                .iter_with(Changed(&comps))
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>();
+            comps.get_mut(e1).unwrap().0 += 1;
+            changed
+        })
+        .system();
+        assert_eq!(mutate_changed.run(&world, ()), vec![e1, e2]);
+        assert_eq!(mutate_changed.run(&world, ()), vec![]);
+
+        // The ticks and the last runs of the systems are preserved in world snapshots, so systems
+        // see the same changes on a snapshot as on the world it was taken from.
+        let snapshot = world.clone();
+        assert_eq!(added.run(&snapshot, ()), vec![]);
+        assert_eq!(added.run(&world, ()), vec![]);
+        assert_eq!(changed.run(&snapshot, ()), vec![e1]);
+        assert_eq!(changed.run(&world, ()), vec![e1]);
+        let snapshot = world.clone();
+        mutate.run(&world, ());
+        assert_eq!(changed.run(&snapshot, ()), vec![]);
+        assert_eq!(changed.run(&world, ()), vec![e2]);
+        // Outside of systems, every component counts as changed.
+        let comps = snapshot.components.get::<A>().borrow();
+        assert!(comps.is_changed(e1) && comps.is_changed(e2));
+    }
+
+    #[test]
+    fn iter_with_without_filters() {
+        #[derive(HasSchema, Clone, Default)]
+        #[repr(C)]
+        struct A(u32);
+        #[derive(HasSchema, Clone, Default)]
+        #[repr(C)]
+        struct B;
+
+        let world = World::new();
+        world.run_system(
+            |mut entities: ResMut<Entities>, mut a: CompMut<A>, mut b: CompMut<B>| {
+                for i in 0..4 {
+                    let e = entities.create();
+                    a.insert(e, A(i));
+                    if i % 2 == 0 {
+                        b.insert(e, B);
+                    }
+                }
+                // An entity with only `B`.
+                let e = entities.create();
+                b.insert(e, B);
+            },
+            (),
+        );
+
+        world.run_system(
+            |entities: Res<Entities>, mut a: CompMut<A>, b: Comp<B>| {
+                let with = entities
+                    .iter_with((&a, With(&b)))
+                    .map(|(_, (a, ()))| a.0)
+                    .collect::<Vec<_>>();
+                assert_eq!(with, [0, 2]);
+
+                for (_, (a, _)) in entities.iter_with((&mut a, Without(&b))) {
+                    a.0 += 10;
+                }
+                let all = entities.iter_with(&a).map(|(_, a)| a.0).collect::<Vec<_>>();
+                assert_eq!(all, [0, 11, 2, 13]);
+
+                let optional = entities
+                    .iter_with((&Optional(&a), With(&b)))
+                    .map(|(_, (a, _))| a.map(|a| a.0))
+                    .collect::<Vec<_>>();
+                assert_eq!(optional, [Some(0), Some(2), None]);
+            },
+            (),
+        );
     }
 
     #[test]
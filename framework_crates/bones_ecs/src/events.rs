@@ -0,0 +1,360 @@
+//! Typed, double-buffered event queues.
+//!
+//! Events are sent with an [`EventWriter`] and read with an [`EventReader`]. Each reader keeps
+//! track of which events it has already read, so every system sees each event exactly once.
+//!
+//! Event buffers are swapped at the end of every [`SystemStages::run()`], so an event stays
+//! readable until the end of the frame after the one it was sent in, and is then dropped.
+//!
+//! Because the events and the reader cursors are stored in a regular [`Events`] resource, they
+//! are cloned along with the [`World`], which makes them safe to use with rollback snapshots.
+
+use crate::prelude::*;
+
+/// A double-buffered queue of events of type `T`, stored as a resource.
+///
+/// You will usually access this through the [`EventWriter`] and [`EventReader`] system
+/// parameters.
+#[derive(HasSchema)]
+#[schema(opaque)]
+#[type_data(<EventsUpdate as FromType<Self>>::from_type())]
+pub struct Events<T> {
+    /// Events sent before the last update.
+    previous: Vec<T>,
+    /// Events sent since the last update.
+    current: Vec<T>,
+    /// The id of the first event in `previous`.
+    ///
+    /// Every event gets an increasing id, which is used to track which events each reader has
+    /// already seen.
+    start_id: usize,
+    /// The id of the next event that each reader has not read yet.
+    ///
+    /// Readers without a cursor haven't read any of the stored events. Cursors are removed once
+    /// they are behind the stored events, so that readers that are dropped don't leak them.
+    cursors: parking_lot::Mutex<HashMap<SystemId, usize>>,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            start_id: 0,
+            cursors: Default::default(),
+        }
+    }
+}
+
+impl<T: Clone> Clone for Events<T> {
+    fn clone(&self) -> Self {
+        Self {
+            previous: self.previous.clone(),
+            current: self.current.clone(),
+            start_id: self.start_id,
+            cursors: parking_lot::Mutex::new(self.cursors.lock().clone()),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Events<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Events")
+            .field("len", &self.len())
+            .field("start_id", &self.start_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Events<T> {
+    /// Send an event.
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// Send a batch of events.
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.current.extend(events);
+    }
+
+    /// Swap the event buffers, dropping the events sent before the previous update.
+    ///
+    /// This is called automatically by [`World::update_events()`].
+    pub fn update(&mut self) {
+        self.start_id += self.previous.len();
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+
+        // A cursor at or before the first stored event is the same as no cursor.
+        let start_id = self.start_id;
+        self.cursors
+            .get_mut()
+            .retain(|_, cursor| *cursor > start_id);
+    }
+
+    /// Remove all of the events.
+    pub fn clear(&mut self) {
+        self.update();
+        self.update();
+    }
+
+    /// Get the number of events currently stored.
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Whether or not there are no events stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over all of the stored events, oldest first, without marking them as read.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(self.current.iter())
+    }
+
+    /// The id that will be given to the next event sent.
+    fn end_id(&self) -> usize {
+        self.start_id + self.len()
+    }
+
+    /// Get the index into [`iter()`][Self::iter] of the first event not yet read by `reader`.
+    fn unread_start(&self, reader: SystemId) -> usize {
+        let cursor = self
+            .cursors
+            .lock()
+            .get(&reader)
+            .copied()
+            .unwrap_or(self.start_id);
+        cursor.saturating_sub(self.start_id)
+    }
+
+    /// Get the number of events that have not been read by the given reader.
+    pub fn unread_len(&self, reader: SystemId) -> usize {
+        self.len() - self.unread_start(reader)
+    }
+
+    /// Iterate over the events that have not yet been read by the given reader, marking them as
+    /// read.
+    pub fn read(&self, reader: SystemId) -> impl Iterator<Item = &T> {
+        let start = self.unread_start(reader);
+        self.mark_read(reader);
+        self.iter().skip(start)
+    }
+
+    /// Mark all of the current events as read by the given reader.
+    pub fn mark_read(&self, reader: SystemId) {
+        self.cursors.lock().insert(reader, self.end_id());
+    }
+}
+
+/// Type data for [`Events`] resources, used to swap their buffers without knowing the event type.
+#[derive(HasSchema, Clone, Copy)]
+#[schema(opaque, no_default)]
+pub struct EventsUpdate {
+    /// Function that calls [`Events::update()`] on a pointer to an [`Events`] resource.
+    pub update_fn: fn(SchemaRefMut),
+}
+
+impl<T: HasSchema + Clone> FromType<Events<T>> for EventsUpdate {
+    fn from_type() -> Self {
+        Self {
+            update_fn: |events| events.cast_into_mut::<Events<T>>().update(),
+        }
+    }
+}
+
+/// [`SystemParam`] for sending events.
+///
+/// The [`Events`] resource will be initialized automatically if it doesn't exist.
+pub struct EventWriter<'a, T: HasSchema + Clone>(RefMut<'a, Events<T>>);
+
+impl<'a, T: HasSchema + Clone> EventWriter<'a, T> {
+    /// Send an event.
+    pub fn send(&mut self, event: T) {
+        self.0.send(event);
+    }
+
+    /// Send a batch of events.
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.0.send_batch(events);
+    }
+}
+
+impl<'a, T: HasSchema + Clone> SystemParam for EventWriter<'a, T> {
+    type State = AtomicResource<Events<T>>;
+    type Param<'s> = EventWriter<'s, T>;
+
+    fn get_state(world: &World) -> Self::State {
+        let cell = world.resources.get_cell::<Events<T>>();
+        cell.init(world);
+        cell
+    }
+
+    fn update_state(world: &World, state: &mut Self::State) {
+        state.init(world);
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.write_resource(Events::<T>::schema());
+    }
+
+    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        EventWriter(state.borrow_mut().unwrap())
+    }
+}
+
+/// [`SystemParam`] for reading events.
+///
+/// Each system keeps its own read cursor, so it will only see each event once.
+///
+/// The [`Events`] resource will be initialized automatically if it doesn't exist.
+pub struct EventReader<'a, T: HasSchema + Clone> {
+    // Borrowed mutably, even though only the read cursors are modified, so that a copy-on-write
+    // snapshot of the events doesn't share its cursors with the world.
+    events: RefMut<'a, Events<T>>,
+    system: SystemId,
+}
+
+impl<'a, T: HasSchema + Clone> EventReader<'a, T> {
+    /// Iterate over the events this system has not read yet, marking them as read.
+    pub fn read(&mut self) -> impl Iterator<Item = &T> {
+        self.events.read(self.system)
+    }
+
+    /// Get the number of events this system has not read yet.
+    pub fn len(&self) -> usize {
+        self.events.unread_len(self.system)
+    }
+
+    /// Whether or not there are no events for this system to read.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Mark all of the pending events as read, without iterating over them.
+    pub fn clear(&mut self) {
+        self.events.mark_read(self.system);
+    }
+}
+
+impl<'a, T: HasSchema + Clone> SystemParam for EventReader<'a, T> {
+    type State = (AtomicResource<Events<T>>, SystemId);
+    type Param<'s> = EventReader<'s, T>;
+
+    fn get_state(world: &World) -> Self::State {
+        Self::get_system_state(world, SystemId::new_unique())
+    }
+
+    fn get_system_state(world: &World, system: SystemId) -> Self::State {
+        let cell = world.resources.get_cell::<Events<T>>();
+        cell.init(world);
+        (cell, system)
+    }
+
+    fn update_state(world: &World, state: &mut Self::State) {
+        state.0.init(world);
+    }
+
+    fn access(access: &mut SystemAccess) {
+        // Initializing the events resource may need to write to it.
+        access.write_resource(Events::<T>::schema());
+    }
+
+    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        EventReader {
+            events: state.0.borrow_mut_untracked().unwrap(),
+            system: state.1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq, Eq)]
+    #[repr(C)]
+    struct Hit(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Received {
+        first: Vec<u32>,
+        last: Vec<u32>,
+    }
+
+    fn send_hit(mut frame: ResMutInit<u32>, mut hits: EventWriter<Hit>) {
+        *frame += 1;
+        hits.send(Hit(*frame));
+    }
+
+    fn read_first(mut hits: EventReader<Hit>, mut received: ResMutInit<Received>) {
+        let hits = hits.read().map(|x| x.0).collect::<Vec<_>>();
+        received.first.extend(hits);
+    }
+
+    fn read_last(mut hits: EventReader<Hit>, mut received: ResMutInit<Received>) {
+        let hits = hits.read().map(|x| x.0).collect::<Vec<_>>();
+        received.last.extend(hits);
+    }
+
+    #[test]
+    fn events_are_read_once_per_reader() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_system_to_stage(First, read_first)
+            .add_system_to_stage(Update, send_hit)
+            .add_system_to_stage(Last, read_last);
+
+        for _ in 0..3 {
+            stages.run(&mut world);
+        }
+
+        let received = world.resource::<Received>();
+        // Events sent after a reader runs are picked up on the next frame.
+        assert_eq!(received.first, vec![1, 2]);
+        assert_eq!(received.last, vec![1, 2, 3]);
+        // Only the events sent during the last frame are still kept around.
+        let events = world.resource::<Events<Hit>>();
+        assert_eq!(events.iter().cloned().collect::<Vec<_>>(), [Hit(3)]);
+    }
+
+    #[test]
+    fn reader_cursors_are_trimmed() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages.add_system_to_stage(Update, send_hit);
+
+        // Every one-off system is a new reader with its own cursor.
+        for _ in 0..10 {
+            stages.run(&mut world);
+            world.run_system(read_last, ());
+        }
+        let events = world.resource::<Events<Hit>>();
+        assert_eq!(events.cursors.lock().len(), 1);
+        drop(events);
+
+        stages.run(&mut world);
+        let events = world.resource::<Events<Hit>>();
+        assert!(events.cursors.lock().is_empty());
+        assert_eq!(events.iter().cloned().collect::<Vec<_>>(), [Hit(11)]);
+    }
+
+    #[test]
+    fn snapshot_restores_reader_cursors() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_system_to_stage(Update, send_hit)
+            .add_system_to_stage(Last, read_last);
+
+        stages.run(&mut world);
+        let mut snapshot = world.clone();
+        stages.run(&mut world);
+        stages.run(&mut snapshot);
+
+        assert_eq!(world.resource::<Received>().last, [1, 2]);
+        assert_eq!(snapshot.resource::<Received>().last, [1, 2]);
+    }
+}
@@ -0,0 +1,160 @@
+//! Parent/child relationships between entities.
+//!
+//! The hierarchy is stored in the [`Parent`] and [`Children`] components, which should only be
+//! modified with [`set_parent()`] and [`remove_parent()`] so that they stay in sync.
+//!
+//! When an entity is killed, [`World::maintain()`] removes it from its parent's [`Children`] and
+//! removes the [`Parent`] component from its children. Use [`Entities::kill_recursive()`] to kill
+//! an entity along with all of its descendants instead.
+
+use crate::prelude::*;
+
+/// Component containing the parent of an entity.
+#[derive(HasSchema, Clone, Copy, Debug, Default, PartialEq, Eq, Deref)]
+#[repr(C)]
+pub struct Parent(pub Entity);
+
+/// Component containing the children of an entity, in the order that they were added.
+#[derive(HasSchema, Clone, Debug, Default, Deref)]
+#[repr(C)]
+pub struct Children(SVec<Entity>);
+
+/// Make `child` a child of `parent`.
+///
+/// If `child` already had a parent, it is removed from that parent's [`Children`] first.
+///
+/// # Panics
+///
+/// Panics if `parent` is `child` or one of its descendants, because that would create a cycle.
+pub fn set_parent(
+    parents: &mut ComponentStore<Parent>,
+    children: &mut ComponentStore<Children>,
+    child: Entity,
+    parent: Entity,
+) {
+    let mut ancestor = Some(parent);
+    while let Some(entity) = ancestor {
+        if entity == child {
+            panic!("Cannot make {parent:?} the parent of {child:?}: it would create a cycle");
+        }
+        ancestor = parents.get(entity).map(|x| x.0);
+    }
+
+    remove_parent(parents, children, child);
+    parents.insert(child, Parent(parent));
+    children.get_mut_or_insert(parent, default).0.push(child);
+}
+
+/// Detach `child` from its parent, returning the parent that it had, if any.
+pub fn remove_parent(
+    parents: &mut ComponentStore<Parent>,
+    children: &mut ComponentStore<Children>,
+    child: Entity,
+) -> Option<Entity> {
+    let Parent(parent) = parents.remove(child)?;
+    if let Some(siblings) = children.get_mut(parent) {
+        siblings.0.retain(|&x| x != child);
+        if siblings.is_empty() {
+            children.remove(parent);
+        }
+    }
+    Some(parent)
+}
+
+/// Remove killed entities from the hierarchy.
+///
+/// This is called by [`World::maintain()`] before the components of the killed entities are
+/// removed.
+pub(crate) fn remove_killed(components: &ComponentStores, killed: &[Entity]) {
+    let mut parents = components.get::<Parent>().borrow_mut();
+    let mut children = components.get::<Children>().borrow_mut();
+
+    for &entity in killed {
+        remove_parent(&mut parents, &mut children, entity);
+        if let Some(orphans) = children.remove(entity) {
+            for &orphan in orphans.iter() {
+                if parents.get(orphan) == Some(&Parent(entity)) {
+                    parents.remove(orphan);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    fn hierarchy_of(world: &World, entity: Entity) -> (Option<Entity>, Vec<Entity>) {
+        let parents = world.components.get::<Parent>().borrow();
+        let children = world.components.get::<Children>().borrow();
+        (
+            parents.get(entity).map(|x| x.0),
+            children
+                .get(entity)
+                .map(|x| x.iter().copied().collect())
+                .unwrap_or_default(),
+        )
+    }
+
+    #[test]
+    fn hierarchy_stays_consistent() {
+        let world = World::new();
+        let [root, a, b, c] = world.run_system(
+            |mut entities: ResMut<Entities>,
+             mut parents: CompMut<Parent>,
+             mut children: CompMut<Children>| {
+                let [root, a, b, c] = std::array::from_fn(|_| entities.create());
+                set_parent(&mut parents, &mut children, a, root);
+                set_parent(&mut parents, &mut children, b, root);
+                set_parent(&mut parents, &mut children, c, a);
+                // Re-parenting removes the entity from its old parent.
+                set_parent(&mut parents, &mut children, c, b);
+                [root, a, b, c]
+            },
+            (),
+        );
+        assert_eq!(hierarchy_of(&world, root), (None, vec![a, b]));
+        assert_eq!(hierarchy_of(&world, a), (Some(root), vec![]));
+        assert_eq!(hierarchy_of(&world, c), (Some(b), vec![]));
+
+        // Killing a child removes it from its parent, and killing a parent orphans its children.
+        let snapshot = world.clone();
+        world.resource_mut::<Entities>().kill(a);
+        world.resource_mut::<Entities>().kill(b);
+        world.maintain();
+        assert_eq!(hierarchy_of(&world, root), (None, vec![]));
+        assert_eq!(hierarchy_of(&world, c), (None, vec![]));
+
+        // The snapshot is unaffected.
+        assert_eq!(hierarchy_of(&snapshot, root), (None, vec![a, b]));
+        assert_eq!(hierarchy_of(&snapshot, c), (Some(b), vec![]));
+
+        // Killing recursively kills all of the descendants.
+        let world = snapshot;
+        world.run_system(
+            move |mut entities: ResMut<Entities>, children: Comp<Children>| {
+                entities.kill_recursive(b, &children);
+            },
+            (),
+        );
+        world.maintain();
+        let entities = world.resource::<Entities>();
+        assert!(entities.is_alive(root) && entities.is_alive(a));
+        assert!(!entities.is_alive(b) && !entities.is_alive(c));
+        drop(entities);
+        assert_eq!(hierarchy_of(&world, root), (None, vec![a]));
+    }
+
+    #[test]
+    #[should_panic(expected = "it would create a cycle")]
+    fn hierarchy_cycle() {
+        let mut entities = Entities::default();
+        let mut parents = ComponentStore::<Parent>::default();
+        let mut children = ComponentStore::<Children>::default();
+        let a = entities.create();
+        let b = entities.create();
+        set_parent(&mut parents, &mut children, b, a);
+        set_parent(&mut parents, &mut children, a, b);
+    }
+}
@@ -0,0 +1,233 @@
+//! Component lifecycle hooks.
+//!
+//! Hooks are registered per component type on a [`World`], with [`World::on_insert()`],
+//! [`World::on_replace()`], [`World::on_remove()`], or [`World::add_component_hook()`] for
+//! untyped components.
+//!
+//! Component stores don't have access to the world, so instead of running the hooks immediately,
+//! the stores record the components that were inserted, replaced or removed. The hooks are run at
+//! the end of every stage, before the stage's [`Commands`] are applied. The hooks for the
+//! components of killed entities, which are removed by [`World::maintain()`], are run at the end of
+//! [`SystemStages::run()`], or at the end of the next stage if the world is maintained manually.
+//! Hooks are given the entity and [`Commands`] that they can use to modify the world.
+//!
+//! The hooks run in the order that the components were inserted, replaced and removed, across all
+//! component types. Changes made by systems that run in parallel are ordered as if the systems
+//! ran one after the other, so the order is deterministic.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::prelude::*;
+
+/// A component hook, called with the entity whose component was inserted, replaced or removed.
+pub type ComponentHook = Arc<dyn Fn(&mut Commands, Entity) + Sync + Send>;
+
+/// The kind of change that triggers a [`ComponentHook`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ComponentHookKind {
+    /// A component was inserted for an entity that didn't have one.
+    Insert,
+    /// A component was inserted for an entity that already had one, replacing it.
+    Replace,
+    /// A component was removed, including when the entity was killed.
+    Remove,
+}
+
+/// The hooks registered for a component type.
+#[derive(Clone, Default)]
+pub struct ComponentHooks {
+    /// Hooks run when a component is inserted for an entity that didn't have one.
+    pub on_insert: Vec<ComponentHook>,
+    /// Hooks run when a component is replaced.
+    pub on_replace: Vec<ComponentHook>,
+    /// Hooks run when a component is removed.
+    pub on_remove: Vec<ComponentHook>,
+}
+
+impl ComponentHooks {
+    /// Get the hooks of the given kind.
+    pub fn get(&self, kind: ComponentHookKind) -> &[ComponentHook] {
+        match kind {
+            ComponentHookKind::Insert => &self.on_insert,
+            ComponentHookKind::Replace => &self.on_replace,
+            ComponentHookKind::Remove => &self.on_remove,
+        }
+    }
+
+    /// Get the hooks of the given kind mutably.
+    pub fn get_mut(&mut self, kind: ComponentHookKind) -> &mut Vec<ComponentHook> {
+        match kind {
+            ComponentHookKind::Insert => &mut self.on_insert,
+            ComponentHookKind::Replace => &mut self.on_replace,
+            ComponentHookKind::Remove => &mut self.on_remove,
+        }
+    }
+}
+
+/// The position of a hook event in the sequence of events of all of the component stores.
+///
+/// Events are ordered by the position of the system that recorded them in its stage first, so
+/// that the events of systems that run in parallel are ordered as if the systems ran in sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct HookEventOrder {
+    system: usize,
+    sequence: u64,
+}
+
+/// The counter for the [`HookEventOrder`] sequence.
+static HOOK_EVENT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The position in its stage of the system running on the current thread.
+    static SYSTEM_POSITION: Cell<usize> = const { Cell::new(0) };
+}
+
+impl HookEventOrder {
+    /// Get the order of a new event, recorded by the system running on the current thread.
+    pub(crate) fn next() -> Self {
+        Self {
+            system: SYSTEM_POSITION.with(|position| position.get()),
+            sequence: HOOK_EVENT_SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Run `f`, ordering the events that it records as coming from the system at `position` in
+    /// its stage.
+    pub(crate) fn with_system_position<R>(position: usize, f: impl FnOnce() -> R) -> R {
+        /// Restores the previous position when dropped, even if the system panics.
+        struct Restore(usize);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SYSTEM_POSITION.with(|position| position.set(self.0));
+            }
+        }
+        let _restore = Restore(SYSTEM_POSITION.with(|x| x.replace(position)));
+        f()
+    }
+}
+
+/// Run the hooks for the events recorded by the component stores, returning whether or not there
+/// were any events.
+///
+/// The events of all of the stores are run in a single sequence, see [`HookEventOrder`].
+pub(crate) fn run_hooks(world: &World) -> bool {
+    let stores = world.components.components.read_only_view();
+    let mut events = Vec::new();
+    for store in stores.values() {
+        let mut store = store.borrow_mut();
+        let hooks = store.hooks.clone();
+        events.extend(
+            std::mem::take(&mut store.hook_events)
+                .into_iter()
+                .map(|(order, kind, entity)| (order, hooks.clone(), kind, entity)),
+        );
+    }
+    drop(stores);
+    if events.is_empty() {
+        return false;
+    }
+    events.sort_by_key(|(order, ..)| *order);
+
+    let mut commands = Commands::new(world);
+    for (_, hooks, kind, entity) in events {
+        for hook in hooks.get(kind) {
+            hook(&mut commands, entity);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Tile(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Log(Vec<String>);
+
+    #[test]
+    fn component_hooks() {
+        let mut world = World::new();
+        for (kind, name) in [
+            (ComponentHookKind::Insert, "insert"),
+            (ComponentHookKind::Replace, "replace"),
+            (ComponentHookKind::Remove, "remove"),
+        ] {
+            world.add_component_hook(
+                Tile::schema(),
+                kind,
+                std::sync::Arc::new(move |commands: &mut Commands, entity: Entity| {
+                    let message = format!("{name} {}", entity.index());
+                    commands.add(move |mut log: ResMutInit<Log>| log.0.push(message.clone()));
+                }),
+            );
+        }
+
+        let mut stages = SystemStages::with_core_stages();
+        stages.add_system_to_stage(Update, |mut commands: Commands| {
+            let entity = commands.spawn().insert(Tile(0)).insert(Tile(1)).id();
+            commands.spawn().insert(Tile(2));
+            commands.entity(entity).remove::<Tile>();
+        });
+        stages.run(&mut world);
+        assert_eq!(
+            world.resource::<Log>().0,
+            ["insert 0", "replace 0", "insert 1", "remove 0"]
+        );
+
+        // Killed entities have their components removed in `maintain()`, and the hooks are run
+        // at the next stage boundary.
+        world.resource_mut::<Entities>().kill(Entity::new(1, 0));
+        world.maintain();
+        assert_eq!(world.resource::<Log>().0.len(), 4);
+        SystemStages::with_core_stages().run(&mut world);
+        assert_eq!(world.resource::<Log>().0.last().unwrap(), "remove 1");
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Name;
+
+    #[test]
+    fn hook_order() {
+        let mut world = World::new();
+        for (schema, name) in [(Tile::schema(), "tile"), (Name::schema(), "name")] {
+            world.add_component_hook(
+                schema,
+                ComponentHookKind::Insert,
+                std::sync::Arc::new(move |commands: &mut Commands, entity: Entity| {
+                    let message = format!("{name} {}", entity.index());
+                    commands.add(move |mut log: ResMutInit<Log>| log.0.push(message.clone()));
+                }),
+            );
+        }
+        let entity = world.resource_mut::<Entities>().create();
+
+        let mut stages = SystemStages::with_core_stages();
+        stages.replace_stage(Update, ParallelSystemStage::new(Update));
+        stages
+            .add_system_to_stage(Update, move |mut tiles: CompMut<Tile>| {
+                // The tile is inserted last, but the system comes first in the stage.
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                tiles.insert(entity, Tile(0));
+            })
+            .add_system_to_stage(Update, move |mut names: CompMut<Name>| {
+                names.insert(entity, Name);
+            })
+            .add_system_to_stage(PostUpdate, |mut commands: Commands| {
+                commands.spawn().insert(Tile(1)).insert(Name);
+                commands.spawn().insert(Name).insert(Tile(2));
+            });
+        stages.run(&mut world);
+
+        // The hooks run in the order that the components were inserted in, across component
+        // types, and as if the parallel systems ran in sequence.
+        assert_eq!(
+            world.resource::<Log>().0,
+            ["tile 0", "name 0", "tile 1", "name 1", "name 2", "tile 2"]
+        );
+    }
+}
@@ -13,16 +13,29 @@ pub mod atomic {
 }
 pub mod bitset;
 pub mod components;
+pub mod condition;
+pub mod diff;
 pub mod entities;
+pub mod events;
+pub mod hierarchy;
+pub mod hooks;
+pub mod parallel;
+pub mod query;
+pub mod relation;
 pub mod resources;
+pub mod schedule;
+#[cfg(feature = "serde")]
+pub mod ser_de;
 pub mod stage;
+pub mod state;
 pub mod system;
+pub mod timings;
 
 pub use bones_schema as schema;
 pub use bones_utils as utils;
 
 mod world;
-pub use world::{FromWorld, World};
+pub use world::{FromWorld, World, WorldId};
 
 /// The prelude.
 pub mod prelude {
@@ -33,11 +46,22 @@ pub mod prelude {
     pub use crate::{
         bitset::*,
         components::*,
+        condition::*,
+        diff::*,
         entities::*,
+        events::*,
+        hierarchy::*,
+        hooks::*,
+        parallel::*,
+        query::*,
+        relation::*,
         resources::*,
+        schedule::*,
         stage::{CoreStage::*, *},
+        state::{StateSchedule::*, *},
         system::*,
-        FromWorld, UnwrapMany, World,
+        timings::*,
+        FromWorld, UnwrapMany, World, WorldId,
     };
 
     #[cfg(feature = "derive")]
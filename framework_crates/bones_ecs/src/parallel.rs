@@ -0,0 +1,299 @@
+//! Parallel query iteration.
+//!
+//! [`Entities::par_iter_with()`] works like [`Entities::iter_with()`], but splits the entities
+//! matching the query into batches of [`PAR_BATCH_SIZE`] entity indices, and runs a closure for
+//! every entity of each batch on the [`ComputeTaskPool`][bevy_tasks::ComputeTaskPool]:
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! # #[derive(HasSchema, Clone, Default)]
+//! # #[repr(C)]
+//! # struct Pos(f32);
+//! # #[derive(HasSchema, Clone, Default)]
+//! # #[repr(C)]
+//! # struct Vel(f32);
+//! fn movement(entities: Res<Entities>, mut positions: CompMut<Pos>, velocities: Comp<Vel>) {
+//!     entities.par_iter_with((&mut positions, &velocities), |_entity, (pos, vel)| {
+//!         pos.0 += vel.0;
+//!     });
+//! }
+//! ```
+//!
+//! Components can be borrowed mutably because every entity index belongs to a single batch, so
+//! the closure never gets the same component twice. The batches only depend on the entity
+//! indices, and the values returned by the closure are collected in entity order, so the results
+//! are the same no matter how many threads there are.
+
+use crate::prelude::*;
+
+/// The number of entity indices in each batch of [`Entities::par_iter_with()`].
+///
+/// This is a multiple of the number of bits in a [`BitSetVec`] chunk, so batches never share a
+/// chunk.
+pub const PAR_BATCH_SIZE: usize = 1024;
+
+/// A [`QueryItem`] that can be iterated over in parallel with [`Entities::par_iter_with()`].
+///
+/// # Safety
+///
+/// It must be sound to call [`get()`][Self::get] from several threads at the same time, as long
+/// as it is called with different indices.
+pub unsafe trait ParQueryItem: QueryItem {
+    /// The type of the item yielded for every entity.
+    type ParItem;
+    /// Access to the components of the query that is shared between threads.
+    type Fetch: Sync;
+    /// Get the access to the components of the query.
+    fn fetch(self) -> Self::Fetch;
+    /// Get the item for the entity at the given index.
+    ///
+    /// # Safety
+    ///
+    /// The index must be set in a bitset that was filtered with
+    /// [`apply_bitset()`][QueryItem::apply_bitset], and must not be passed more than once for the
+    /// same fetch.
+    unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem;
+}
+
+unsafe impl<'a, 'q, T: HasSchema> ParQueryItem for &'a Comp<'q, T> {
+    type ParItem = &'a T;
+    type Fetch = ParComponents<'a, T>;
+    fn fetch(self) -> Self::Fetch {
+        ParComponents::new(self)
+    }
+
+    unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem {
+        // SAFE: the bitset has been filtered by the store's bitset.
+        fetch.get(index).unwrap_unchecked()
+    }
+}
+
+unsafe impl<'a, 'q, T: HasSchema> ParQueryItem for &'a CompMut<'q, T> {
+    type ParItem = &'a T;
+    type Fetch = ParComponents<'a, T>;
+    fn fetch(self) -> Self::Fetch {
+        ParComponents::new(self)
+    }
+
+    unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem {
+        // SAFE: the bitset has been filtered by the store's bitset.
+        fetch.get(index).unwrap_unchecked()
+    }
+}
+
+unsafe impl<'a, 'q, T: HasSchema> ParQueryItem for &'a mut CompMut<'q, T> {
+    type ParItem = &'a mut T;
+    type Fetch = ParComponentsMut<'a, T>;
+    fn fetch(self) -> Self::Fetch {
+        ParComponentsMut::new(self)
+    }
+
+    unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem {
+        // SAFE: the bitset has been filtered by the store's bitset, and the caller ensures that
+        // the index is only borrowed once.
+        fetch.get(index).unwrap_unchecked()
+    }
+}
+
+unsafe impl<'a, T: HasSchema, S, C> ParQueryItem for &'a OptionalQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type ParItem = Option<&'a T>;
+    type Fetch = ParComponents<'a, T>;
+    fn fetch(self) -> Self::Fetch {
+        ParComponents::new(self.0.component_store())
+    }
+
+    unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem {
+        fetch.get(index)
+    }
+}
+
+unsafe impl<'a, T: HasSchema, S> ParQueryItem for &'a mut OptionalQueryItemMut<'a, T, S>
+where
+    S: std::ops::DerefMut<Target = ComponentStore<T>> + 'a,
+{
+    type ParItem = Option<&'a mut T>;
+    type Fetch = ParComponentsMut<'a, T>;
+    fn fetch(self) -> Self::Fetch {
+        ParComponentsMut::new(&mut *self.0)
+    }
+
+    unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem {
+        // SAFE: the caller ensures that the index is only borrowed once.
+        fetch.get(index)
+    }
+}
+
+unsafe impl<'a, T: HasSchema, S, C> ParQueryItem for AddedQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type ParItem = &'a T;
+    type Fetch = ParComponents<'a, T>;
+    fn fetch(self) -> Self::Fetch {
+        ParComponents::new(self.0.component_store())
+    }
+
+    unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem {
+        // SAFE: the bitset has been filtered by the entities that have the component.
+        fetch.get(index).unwrap_unchecked()
+    }
+}
+
+unsafe impl<'a, T: HasSchema, S, C> ParQueryItem for ChangedQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type ParItem = &'a T;
+    type Fetch = ParComponents<'a, T>;
+    fn fetch(self) -> Self::Fetch {
+        ParComponents::new(self.0.component_store())
+    }
+
+    unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem {
+        // SAFE: the bitset has been filtered by the entities that have the component.
+        fetch.get(index).unwrap_unchecked()
+    }
+}
+
+unsafe impl<'a, T: HasSchema, S, C> ParQueryItem for WithQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type ParItem = ();
+    type Fetch = ();
+    fn fetch(self) -> Self::Fetch {}
+
+    unsafe fn get(_fetch: &Self::Fetch, _index: usize) -> Self::ParItem {}
+}
+
+unsafe impl<'a, T: HasSchema, S, C> ParQueryItem for WithoutQueryItem<'a, T, S>
+where
+    C: ComponentIterBitset<'a, T> + 'a,
+    S: std::ops::Deref<Target = C> + 'a,
+{
+    type ParItem = ();
+    type Fetch = ();
+    fn fetch(self) -> Self::Fetch {}
+
+    unsafe fn get(_fetch: &Self::Fetch, _index: usize) -> Self::ParItem {}
+}
+
+macro_rules! impl_par_query {
+    ( $( $args:ident, )* ) => {
+        unsafe impl<
+            $(
+                $args: ParQueryItem,
+            )*
+        > ParQueryItem for (
+            $(
+                $args,
+            )*
+        ) {
+            type ParItem = (
+                $(
+                    $args::ParItem,
+                )*
+            );
+            type Fetch = (
+                $(
+                    $args::Fetch,
+                )*
+            );
+
+            #[allow(non_snake_case)]
+            fn fetch(self) -> Self::Fetch {
+                let (
+                    $(
+                        $args,
+                    )*
+                ) = self;
+                (
+                    $(
+                        $args.fetch(),
+                    )*
+                )
+            }
+
+            #[allow(non_snake_case)]
+            unsafe fn get(fetch: &Self::Fetch, index: usize) -> Self::ParItem {
+                let (
+                    $(
+                        $args,
+                    )*
+                ) = fetch;
+                (
+                    $(
+                        $args::get($args, index),
+                    )*
+                )
+            }
+        }
+    };
+}
+
+macro_rules! impl_par_queries {
+    // base case
+    () => {};
+    (
+        $head:ident,
+        $(
+            $tail:ident,
+        )*
+    ) => {
+        // recursive call
+        impl_par_query!($head, $( $tail, )* );
+        impl_par_queries!($( $tail, )* );
+    }
+}
+
+impl_par_queries!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,);
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Pos(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Vel(u32);
+
+    #[test]
+    fn par_iter_with() {
+        let world = World::new();
+        let total = world.run_system(
+            |mut entities: ResMut<Entities>, mut pos: CompMut<Pos>, mut vel: CompMut<Vel>| {
+                for i in 0..5000 {
+                    let entity = entities.create();
+                    pos.insert(entity, Pos(i));
+                    if i % 3 == 0 {
+                        vel.insert(entity, Vel(2));
+                    }
+                }
+
+                let results = entities.par_iter_with((&mut pos, &vel), |entity, (pos, vel)| {
+                    pos.0 += vel.0;
+                    (entity.index(), pos.0)
+                });
+                // The results are in entity order, and the components have been updated.
+                assert_eq!(results.len(), 1667);
+                assert!(results.windows(2).all(|pair| pair[0].0 < pair[1].0));
+                assert!(results.iter().all(|&(index, pos)| pos == index + 2));
+
+                entities
+                    .par_iter_with(&Optional(&vel), |_, vel| vel.map_or(0, |vel| vel.0))
+                    .into_iter()
+                    .sum::<u32>()
+            },
+            (),
+        );
+        assert_eq!(total, 1667 * 2);
+    }
+}
@@ -0,0 +1,371 @@
+//! Cached queries.
+//!
+//! [`Entities::iter_with()`] builds the bitset of the entities to iterate over every time that it
+//! is called, by cloning the bitset of the alive entities and joining it with the bitset of every
+//! component in the query. A [`QueryCache`] keeps that bitset between calls instead, and only
+//! updates it for the entities that were created or killed, or that had a component of the query
+//! inserted or removed, since it was last used:
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! # #[derive(HasSchema, Clone, Default)]
+//! # #[repr(C)]
+//! # struct Pos(f32);
+//! # #[derive(HasSchema, Clone, Default)]
+//! # #[repr(C)]
+//! # struct Vel(f32);
+//! fn movement(
+//!     mut cache: Local<QueryCache>,
+//!     entities: Res<Entities>,
+//!     mut positions: CompMut<Pos>,
+//!     velocities: Comp<Vel>,
+//! ) {
+//!     for (_, (pos, vel)) in entities.iter_with_cache(&mut cache, (&mut positions, &velocities)) {
+//!         pos.0 += vel.0;
+//!     }
+//! }
+//! ```
+//!
+//! [`Entities`] and the component stores log the entities whose bit changed in their bitset. Like
+//! [`Events`], the logs are double-buffered and swapped in [`World::maintain()`], so a cache that
+//! hasn't been used for two frames, or that is used with another query or with a world that has
+//! diverged from the one it was last used with, is rebuilt from scratch. A clone of a log keeps the
+//! changes that it shares with the original, so taking a snapshot of the world doesn't invalidate
+//! the caches of the world, even once its [`Entities`] or its stores are copied on write.
+//!
+//! The [`Added`] and [`Changed`] filters depend on the change ticks, so they are not cached, and
+//! are applied to a copy of the cached bitset every time the query is run.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::prelude::*;
+
+/// The id of the next [`ChangeLog`] to be created.
+static NEXT_CHANGE_LOG_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A log of the entity indices whose bit changed in a bitset, used to update [`QueryCache`]s.
+///
+/// Every log has a unique id, which is not shared with its clones, so that caches built from
+/// another log are rebuilt. A clone remembers the logs that it was cloned from, and the changes
+/// that it shares with them, so that caches built from the original are still up to date with it.
+#[derive(Debug)]
+pub(crate) struct ChangeLog {
+    id: u64,
+    /// The ends of the logs that this log was cloned from, at the time that it was cloned.
+    forks: Vec<ChangeCursor>,
+    /// The position of the first index in `previous`.
+    start: usize,
+    /// Indices logged before the last update.
+    previous: Vec<u32>,
+    /// Indices logged since the last update.
+    current: Vec<u32>,
+}
+
+impl Default for ChangeLog {
+    fn default() -> Self {
+        Self {
+            id: NEXT_CHANGE_LOG_ID.fetch_add(1, Ordering::Relaxed),
+            forks: Vec::new(),
+            start: 0,
+            previous: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+}
+
+impl Clone for ChangeLog {
+    fn clone(&self) -> Self {
+        let mut forks = self.forks.clone();
+        forks.push(self.end());
+        Self {
+            forks,
+            start: self.start,
+            previous: self.previous.clone(),
+            current: self.current.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+impl ChangeLog {
+    /// Log a change of the bit at the given index.
+    #[inline]
+    pub fn push(&mut self, index: usize) {
+        self.current.push(index as u32);
+    }
+
+    /// Swap the buffers, forgetting the changes logged before the previous update.
+    pub fn update(&mut self) {
+        self.start += self.previous.len();
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+        self.forks.retain(|fork| fork.position >= self.start);
+    }
+
+    /// The cursor pointing after the last logged change.
+    fn end(&self) -> ChangeCursor {
+        ChangeCursor {
+            log: self.id,
+            position: self.start + self.previous.len() + self.current.len(),
+        }
+    }
+
+    /// Iterate over the indices logged after the cursor, or return [`None`] if the cursor points
+    /// to a log that this log doesn't share the changes with, or to changes that have already been
+    /// forgotten.
+    fn since(&self, cursor: ChangeCursor) -> Option<impl Iterator<Item = usize> + '_> {
+        let shared = cursor.log == self.id
+            || self
+                .forks
+                .iter()
+                .any(|fork| fork.log == cursor.log && cursor.position <= fork.position);
+        if !shared || cursor.position < self.start {
+            return None;
+        }
+        let skip = cursor.position - self.start;
+        Some(
+            self.previous
+                .iter()
+                .chain(&self.current)
+                .skip(skip)
+                .map(|&index| index as usize),
+        )
+    }
+}
+
+/// A position in a [`ChangeLog`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ChangeCursor {
+    log: u64,
+    position: usize,
+}
+
+/// The component stores that filter the entities of a query, collected with
+/// [`QueryItem::cache_filter()`].
+#[derive(Default)]
+pub struct QueryFilter<'a> {
+    with: Vec<&'a UntypedComponentStore>,
+    without: Vec<&'a UntypedComponentStore>,
+    uncached: bool,
+}
+
+impl<'a> QueryFilter<'a> {
+    /// Only match the entities that have a component in the store.
+    pub fn with(&mut self, store: &'a UntypedComponentStore) {
+        self.with.push(store);
+    }
+
+    /// Only match the entities that don't have a component in the store.
+    pub fn without(&mut self, store: &'a UntypedComponentStore) {
+        self.without.push(store);
+    }
+
+    /// Mark the query as having filters that can't be cached, so that
+    /// [`QueryItem::apply_bitset()`] is called every time the query is run.
+    pub fn uncached(&mut self) {
+        self.uncached = true;
+    }
+
+    /// Whether or not [`uncached()`][Self::uncached] has been called.
+    pub fn is_uncached(&self) -> bool {
+        self.uncached
+    }
+
+    /// The schemas of the stores in the filter, used to check that a cache is used for the same
+    /// query every time.
+    fn key(&self) -> impl Iterator<Item = (SchemaId, bool)> + '_ {
+        let with = self.with.iter().map(|store| (store.schema().id(), true));
+        let without = self
+            .without
+            .iter()
+            .map(|store| (store.schema().id(), false));
+        with.chain(without)
+    }
+
+    /// Whether or not the entity at the given index matches the filter.
+    fn matches(&self, entities: &Entities, index: usize) -> bool {
+        entities.bitset().bit_test(index)
+            && self.with.iter().all(|store| store.bitset().bit_test(index))
+            && !self
+                .without
+                .iter()
+                .any(|store| store.bitset().bit_test(index))
+    }
+}
+
+/// The cached bitset of the entities matching a query.
+///
+/// See the [module documentation][self].
+#[derive(Clone, Debug, Default)]
+pub struct QueryCache {
+    bitset: BitSetVec,
+    /// The schemas of the stores in the filter that the cache was built for.
+    key: Vec<(SchemaId, bool)>,
+    /// The cursors into the change logs of the entities and the component stores, pointing after
+    /// the changes that the bitset is up to date with.
+    cursors: Vec<ChangeCursor>,
+}
+
+impl QueryCache {
+    /// Get the cached bitset, as of the last time that the cache was used.
+    pub fn bitset(&self) -> &BitSetVec {
+        &self.bitset
+    }
+
+    /// Bring the cached bitset up to date.
+    pub(crate) fn update(&mut self, entities: &Entities, filter: &QueryFilter) {
+        let same_query = self.key.iter().copied().eq(filter.key());
+        if !same_query || self.cursors.is_empty() {
+            return self.rebuild(entities, filter);
+        }
+
+        let mut changed = Vec::new();
+        for (log, &cursor) in change_logs(entities, filter).zip(&self.cursors) {
+            let Some(indices) = log.since(cursor) else {
+                return self.rebuild(entities, filter);
+            };
+            changed.extend(indices);
+        }
+        for index in changed {
+            self.bitset.bit_cond(index, filter.matches(entities, index));
+        }
+        self.cursors.clear();
+        self.cursors
+            .extend(change_logs(entities, filter).map(ChangeLog::end));
+    }
+
+    /// Rebuild the cached bitset from scratch.
+    fn rebuild(&mut self, entities: &Entities, filter: &QueryFilter) {
+        self.bitset.clone_from(entities.bitset());
+        for store in &filter.with {
+            self.bitset.bit_and(store.bitset());
+        }
+        for store in &filter.without {
+            self.bitset.bit_andnot(store.bitset());
+        }
+        self.key = filter.key().collect();
+        self.cursors = change_logs(entities, filter).map(ChangeLog::end).collect();
+    }
+}
+
+/// Get the change logs of the entities and of the component stores in the filter.
+fn change_logs<'a>(
+    entities: &'a Entities,
+    filter: &'a QueryFilter,
+) -> impl Iterator<Item = &'a ChangeLog> {
+    let stores = filter.with.iter().chain(&filter.without);
+    std::iter::once(&entities.changes).chain(stores.map(|store| &store.changes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct A(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    struct B;
+
+    #[test]
+    fn query_cache() {
+        let world = World::new();
+        let mut query =
+            (|mut cache: Local<QueryCache>, entities: Res<Entities>, a: Comp<A>, b: Comp<B>| {
+                entities
+                    .iter_with_cache(&mut cache, (&a, Without(&b)))
+                    .map(|(_, (a, _))| a.0)
+                    .collect::<Vec<_>>()
+            })
+            .system();
+
+        let [e1, e2, e3] = world.run_system(
+            |mut entities: ResMut<Entities>, mut a: CompMut<A>, mut b: CompMut<B>| {
+                let [e1, e2, e3] = std::array::from_fn(|_| entities.create());
+                a.insert(e1, A(1));
+                a.insert(e2, A(2));
+                b.insert(e2, B);
+                [e1, e2, e3]
+            },
+            (),
+        );
+        assert_eq!(query.run(&world, ()), [1]);
+
+        // The cache is updated from the changes made since it was last used.
+        world.run_system(
+            move |mut entities: ResMut<Entities>, mut a: CompMut<A>, mut b: CompMut<B>| {
+                a.insert(e3, A(3));
+                b.remove(e2);
+                entities.kill(e1);
+            },
+            (),
+        );
+        assert_eq!(query.run(&world, ()), [2, 3]);
+
+        // It is rebuilt if changes have been forgotten, or if it is used with a world that has
+        // diverged.
+        for _ in 0..3 {
+            world.maintain();
+        }
+        world.run_system(move |mut b: CompMut<B>| b.insert(e3, B), ());
+        world.maintain();
+        world.maintain();
+        assert_eq!(query.run(&world, ()), [2]);
+        let clone = world.clone();
+        clone.run_system(move |mut b: CompMut<B>| b.insert(e2, B), ());
+        assert_eq!(query.run(&clone, ()), Vec::<u32>::new());
+        assert_eq!(query.run(&world, ()), [2]);
+    }
+
+    #[test]
+    fn query_cache_snapshot() {
+        let world = World::new();
+        let mut cache = QueryCache::default();
+        fn query(world: &World, cache: &mut QueryCache) -> Vec<u32> {
+            let entities = world.resource::<Entities>();
+            let a: Comp<A> = world.components.get::<A>().borrow();
+            let values = entities
+                .iter_with_cache(cache, &a)
+                .map(|(_, a)| a.0)
+                .collect();
+            values
+        }
+        // Whether or not the cache can be updated from the changes of the world.
+        fn up_to_date(world: &World, cache: &QueryCache) -> bool {
+            let entities = world.resource::<Entities>();
+            let a = world.components.get::<A>().borrow();
+            let mut filter = QueryFilter::default();
+            filter.with(a.as_untyped());
+            let up_to_date = super::change_logs(&entities, &filter)
+                .zip(&cache.cursors)
+                .all(|(log, &cursor)| log.since(cursor).is_some());
+            up_to_date
+        }
+
+        world.run_system(
+            |mut entities: ResMut<Entities>, mut a: CompMut<A>| {
+                let e = entities.create();
+                a.insert(e, A(1));
+            },
+            (),
+        );
+        assert_eq!(query(&world, &mut cache), [1]);
+
+        // Taking a snapshot and copying the entities and the store on write doesn't invalidate
+        // the cache.
+        let snapshot = world.clone();
+        world.run_system(
+            |mut entities: ResMut<Entities>, mut a: CompMut<A>| {
+                let e = entities.create();
+                a.insert(e, A(2));
+            },
+            (),
+        );
+        assert!(up_to_date(&world, &cache));
+        assert_eq!(query(&world, &mut cache), [1, 2]);
+
+        // The snapshot has diverged from the world, so the cache is rebuilt for it.
+        assert!(!up_to_date(&snapshot, &cache));
+        assert_eq!(query(&snapshot, &mut cache), [1]);
+    }
+}
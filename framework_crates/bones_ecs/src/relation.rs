@@ -0,0 +1,463 @@
+//! Relations between entities.
+//!
+//! A [`Relation<R>`] maps source entities to target entities, for a marker type `R` that
+//! distinguishes the different kinds of relations. Unlike a component holding an [`Entity`], it
+//! also indexes the sources that point at every target, and it is cleaned up when entities are
+//! killed:
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! /// Relation from a projectile to the entity that fired it.
+//! #[derive(HasSchema, Clone, Default)]
+//! struct FiredBy;
+//!
+//! let mut world = World::new();
+//! world.insert_resource(Relation::<FiredBy>::new(OnTargetKilled::KillSource));
+//! world.run_system(
+//!     |mut entities: ResMut<Entities>, mut fired_by: ResMut<Relation<FiredBy>>| {
+//!         let player = entities.create();
+//!         let bullet = entities.create();
+//!         fired_by.insert(bullet, player);
+//!         assert_eq!(fired_by.sources(player), [bullet]);
+//!     },
+//!     (),
+//! );
+//! ```
+//!
+//! When an entity is killed, [`World::maintain()`] removes the relations that it is the source
+//! of, and applies the [`OnTargetKilled`] behavior of every relation to the relations that it is
+//! the target of. Sources killed because of [`OnTargetKilled::KillSource`] are cleaned up in the
+//! same way, before any components are removed.
+//!
+//! Relations are stored in resources, so they are saved and restored along with world snapshots,
+//! and they are serialized and compared along with the world.
+
+use std::{
+    alloc::Layout,
+    any::{type_name, TypeId},
+    marker::PhantomData,
+    sync::OnceLock,
+};
+
+use bones_schema::{
+    alloc::TypeDatas,
+    raw_fns::{RawClone, RawDefault, RawDrop},
+};
+use bones_utils::parking_lot::RwLock;
+
+use crate::prelude::*;
+
+/// What happens to the relations pointing at an entity when it is killed.
+#[derive(HasSchema, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OnTargetKilled {
+    /// Remove the relations.
+    #[default]
+    Remove,
+    /// Kill the sources of the relations.
+    KillSource,
+    /// Keep the relations, pointing at the dead entity.
+    Keep,
+}
+
+/// Resource containing the relations of kind `R`, from source entities to target entities.
+///
+/// Every source has at most one target, and a target may have any number of sources.
+///
+/// See the [module documentation][self].
+#[repr(C)]
+pub struct Relation<R> {
+    /// What happens to the relations pointing at an entity when it is killed.
+    pub on_target_killed: OnTargetKilled,
+    /// The target of every source, sorted by source.
+    targets: SVec<RelationTarget>,
+    /// The sources of every target, sorted by target, with the sources of each target in the
+    /// order that the relations were inserted.
+    sources: SVec<RelationSources>,
+    _kind: PhantomData<R>,
+}
+
+/// A source and its target, in a [`Relation`].
+#[derive(HasSchema, Clone, Copy, Debug, Default)]
+#[repr(C)]
+struct RelationTarget {
+    source: Entity,
+    target: Entity,
+}
+
+/// A target and its sources, in a [`Relation`].
+#[derive(HasSchema, Clone, Debug, Default)]
+#[repr(C)]
+struct RelationSources {
+    target: Entity,
+    sources: SVec<Entity>,
+}
+
+// SAFE: We return a valid schema. `Relation<R>` is `repr(C)` and its last field is zero-sized, so
+// it has the same layout as a struct with the fields of the schema.
+unsafe impl<R: HasSchema + Clone> HasSchema for Relation<R> {
+    fn schema() -> &'static Schema {
+        static SCHEMAS: OnceLock<RwLock<HashMap<TypeId, &'static Schema>>> = OnceLock::new();
+
+        let map = SCHEMAS.get_or_init(|| RwLock::new(HashMap::default()));
+        let existing_schema = { map.read().get(&TypeId::of::<Self>()).copied() };
+        if let Some(existing_schema) = existing_schema {
+            return existing_schema;
+        }
+
+        // The schema can't be derived, because the marker field doesn't have a schema.
+        let type_data = TypeDatas::default();
+        type_data
+            .insert(<RelationCleanup as FromType<Self>>::from_type())
+            .unwrap();
+        let field = |name: &str, schema| StructFieldInfo {
+            name: Some(name.into()),
+            schema,
+        };
+        let schema = SCHEMA_REGISTRY.register(SchemaData {
+            name: "Relation".into(),
+            full_name: type_name::<Self>().into(),
+            type_id: Some(TypeId::of::<Self>()),
+            kind: SchemaKind::Struct(StructSchemaInfo {
+                fields: vec![
+                    field("on_target_killed", OnTargetKilled::schema()),
+                    field("targets", SVec::<RelationTarget>::schema()),
+                    field("sources", SVec::<RelationSources>::schema()),
+                ],
+            }),
+            clone_fn: Some(<Self as RawClone>::raw_clone_cb()),
+            drop_fn: Some(<Self as RawDrop>::raw_drop_cb()),
+            default_fn: Some(<Self as RawDefault>::raw_default_cb()),
+            eq_fn: None,
+            hash_fn: None,
+            type_data,
+        });
+        assert_eq!(schema.layout(), Layout::new::<Self>());
+        map.write().insert(TypeId::of::<Self>(), schema);
+        schema
+    }
+}
+
+impl<R> Default for Relation<R> {
+    fn default() -> Self {
+        Self::new(OnTargetKilled::default())
+    }
+}
+
+impl<R> Clone for Relation<R> {
+    fn clone(&self) -> Self {
+        Self {
+            on_target_killed: self.on_target_killed,
+            targets: self.targets.clone(),
+            sources: self.sources.clone(),
+            _kind: PhantomData,
+        }
+    }
+}
+
+impl<R> std::fmt::Debug for Relation<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Relation")
+            .field("on_target_killed", &self.on_target_killed)
+            .field("targets", &self.targets)
+            .finish()
+    }
+}
+
+impl<R> Relation<R> {
+    /// Create an empty relation with the given behavior for killed targets.
+    pub fn new(on_target_killed: OnTargetKilled) -> Self {
+        Self {
+            on_target_killed,
+            targets: SVec::new(),
+            sources: SVec::new(),
+            _kind: PhantomData,
+        }
+    }
+
+    /// Make `source` point at `target`, returning the previous target of `source`, if any.
+    pub fn insert(&mut self, source: Entity, target: Entity) -> Option<Entity> {
+        let previous = self.remove(source);
+        let i = self.target_index(source).unwrap_err();
+        self.targets.push(RelationTarget { source, target });
+        (*self.targets)[i..].rotate_right(1);
+        match self.sources_index(target) {
+            Ok(i) => self.sources[i].sources.push(source),
+            Err(i) => {
+                self.sources.push(RelationSources {
+                    target,
+                    sources: [source].into(),
+                });
+                (*self.sources)[i..].rotate_right(1);
+            }
+        }
+        previous
+    }
+
+    /// Remove the relation from `source`, returning its target, if any.
+    pub fn remove(&mut self, source: Entity) -> Option<Entity> {
+        let i = self.target_index(source).ok()?;
+        let target = self.targets.remove(i).target;
+        if let Ok(i) = self.sources_index(target) {
+            let sources = &mut self.sources[i].sources;
+            sources.retain(|&x| x != source);
+            if sources.is_empty() {
+                self.sources.remove(i);
+            }
+        }
+        Some(target)
+    }
+
+    /// Get the target that `source` points at.
+    pub fn get(&self, source: Entity) -> Option<Entity> {
+        let i = self.target_index(source).ok()?;
+        Some(self.targets[i].target)
+    }
+
+    /// Get the sources that point at `target`, in the order that the relations were inserted.
+    pub fn sources(&self, target: Entity) -> &[Entity] {
+        match self.sources_index(target) {
+            Ok(i) => &self.sources[i].sources,
+            Err(_) => &[],
+        }
+    }
+
+    /// Iterate over the `(source, target)` pairs, ordered by source.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.targets.iter().map(|x| (x.source, x.target))
+    }
+
+    /// Get the number of relations.
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Whether or not there are no relations.
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Remove the relations of the killed entities, applying the
+    /// [`on_target_killed`][Self::on_target_killed] behavior to the relations pointing at them.
+    pub fn remove_killed(&mut self, killed: &[Entity], entities: &mut Entities) {
+        for &entity in killed {
+            self.remove(entity);
+            if self.on_target_killed == OnTargetKilled::Keep {
+                continue;
+            }
+            let Ok(i) = self.sources_index(entity) else {
+                continue;
+            };
+            for source in self.sources.remove(i).sources {
+                if let Ok(i) = self.target_index(source) {
+                    self.targets.remove(i);
+                }
+                if self.on_target_killed == OnTargetKilled::KillSource && entities.is_alive(source)
+                {
+                    entities.kill(source);
+                }
+            }
+        }
+    }
+
+    /// Whether or not [`remove_killed()`][Self::remove_killed] would change the relations.
+    fn is_affected_by(&self, killed: &[Entity]) -> bool {
+        killed.iter().any(|&entity| {
+            self.target_index(entity).is_ok()
+                || (self.on_target_killed != OnTargetKilled::Keep
+                    && self.sources_index(entity).is_ok())
+        })
+    }
+
+    /// Find the index of `source` in the targets, or the index where it would be inserted.
+    fn target_index(&self, source: Entity) -> Result<usize, usize> {
+        self.targets.binary_search_by_key(&source, |x| x.source)
+    }
+
+    /// Find the index of `target` in the sources, or the index where it would be inserted.
+    fn sources_index(&self, target: Entity) -> Result<usize, usize> {
+        self.sources.binary_search_by_key(&target, |x| x.target)
+    }
+}
+
+/// Type data for [`Relation`] resources, used to clean them up without knowing the relation kind.
+#[derive(HasSchema, Clone, Copy)]
+#[schema(opaque, no_default)]
+pub struct RelationCleanup {
+    /// Function that checks whether or not [`Relation::remove_killed()`] would change a
+    /// [`Relation`] resource, so that it is only borrowed mutably when needed.
+    pub is_affected_fn: fn(SchemaRef, &[Entity]) -> bool,
+    /// Function that calls [`Relation::remove_killed()`] on a pointer to a [`Relation`] resource.
+    pub remove_killed_fn: fn(SchemaRefMut, &[Entity], &mut Entities),
+}
+
+impl<R: HasSchema + Clone> FromType<Relation<R>> for RelationCleanup {
+    fn from_type() -> Self {
+        Self {
+            is_affected_fn: |relation, killed| {
+                relation.cast::<Relation<R>>().is_affected_by(killed)
+            },
+            remove_killed_fn: |relation, killed, entities| {
+                relation
+                    .cast_into_mut::<Relation<R>>()
+                    .remove_killed(killed, entities)
+            },
+        }
+    }
+}
+
+/// Clean up the relations of the killed entities, until no more sources are killed.
+///
+/// This is called by [`World::maintain()`] before the killed entities are removed from the
+/// hierarchy.
+pub(crate) fn remove_killed(resources: &Resources, entities: &mut Entities) {
+    let mut start = 0;
+    while start < entities.killed().len() {
+        let killed = entities.killed()[start..].to_vec();
+        start = entities.killed().len();
+        for cell in resources.untyped().resources.read_only_view().values() {
+            let Some(cleanup) = cell.schema().type_data.get::<RelationCleanup>() else {
+                continue;
+            };
+            // Borrowing the resource mutably would copy it if it is shared with a snapshot, so it
+            // is only done if the relation has to change.
+            let affected = cell
+                .borrow()
+                .as_ref()
+                .is_some_and(|relation| (cleanup.is_affected_fn)(relation.as_ref(), &killed));
+            if !affected {
+                continue;
+            }
+            if let Some(relation) = cell.borrow_mut().as_mut() {
+                (cleanup.remove_killed_fn)(relation.as_mut(), &killed, entities);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Targets;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct OwnedBy;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Remembers;
+
+    #[test]
+    fn relation_cleanup() {
+        let mut world = World::new();
+        world.insert_resource(Relation::<OwnedBy>::new(OnTargetKilled::KillSource));
+        world.insert_resource(Relation::<Remembers>::new(OnTargetKilled::Keep));
+        let [player, enemy, sword, gem] = world.run_system(
+            |mut entities: ResMut<Entities>,
+             mut targets: ResMutInit<Relation<Targets>>,
+             mut owned_by: ResMut<Relation<OwnedBy>>,
+             mut remembers: ResMut<Relation<Remembers>>| {
+                let [player, enemy, sword, gem] = std::array::from_fn(|_| entities.create());
+                targets.insert(player, enemy);
+                targets.insert(sword, enemy);
+                owned_by.insert(sword, enemy);
+                owned_by.insert(gem, sword);
+                remembers.insert(player, enemy);
+                // Inserting a relation again replaces the target.
+                assert_eq!(targets.insert(sword, player), Some(enemy));
+                [player, enemy, sword, gem]
+            },
+            (),
+        );
+        let snapshot = world.clone();
+
+        world.resource_mut::<Entities>().kill(enemy);
+        world.maintain();
+
+        // The relations pointing at the enemy are removed, kept, or have their sources killed,
+        // recursively.
+        let entities = world.resource::<Entities>();
+        assert!(entities.is_alive(player));
+        assert!(!entities.is_alive(sword) && !entities.is_alive(gem));
+        drop(entities);
+        assert!(world.resource::<Relation<Targets>>().is_empty());
+        assert!(world.resource::<Relation<OwnedBy>>().is_empty());
+        assert_eq!(
+            world.resource::<Relation<Remembers>>().get(player),
+            Some(enemy)
+        );
+
+        // The snapshot still has all of the relations.
+        let targets = snapshot.resource::<Relation<Targets>>();
+        assert_eq!(targets.sources(enemy), [player]);
+        assert_eq!(targets.sources(player), [sword]);
+        let owned_by = snapshot.resource::<Relation<OwnedBy>>();
+        assert_eq!(
+            owned_by.iter().collect::<Vec<_>>(),
+            [(sword, enemy), (gem, sword)]
+        );
+    }
+
+    #[test]
+    fn relation_cleanup_only_borrows_affected_relations() {
+        let mut world = World::new();
+        world.init_resource::<Relation<Targets>>();
+        let [player, enemy, bystander] = world.run_system(
+            |mut entities: ResMut<Entities>, mut targets: ResMut<Relation<Targets>>| {
+                let entities = std::array::from_fn(|_| entities.create());
+                targets.insert(entities[0], entities[1]);
+                entities
+            },
+            (),
+        );
+        let resource = world.resources.untyped().get(Relation::<Targets>::schema());
+        let change_count = resource.change_count();
+
+        // Killing an entity that isn't in the relation doesn't borrow it mutably, so it isn't
+        // copied if it is shared with a snapshot.
+        world.resource_mut::<Entities>().kill(bystander);
+        world.maintain();
+        assert_eq!(resource.change_count(), change_count);
+
+        world.resource_mut::<Entities>().kill(enemy);
+        world.maintain();
+        assert!(resource.change_count() > change_count);
+        assert_eq!(world.resource::<Relation<Targets>>().get(player), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn relation_save_load() {
+        let mut world = World::new();
+        world.insert_resource(Relation::<OwnedBy>::new(OnTargetKilled::KillSource));
+        let [player, sword, gem] = world.run_system(
+            |mut entities: ResMut<Entities>, mut owned_by: ResMut<Relation<OwnedBy>>| {
+                let [player, sword, gem] = std::array::from_fn(|_| entities.create());
+                owned_by.insert(gem, player);
+                owned_by.insert(sword, player);
+                [player, sword, gem]
+            },
+            (),
+        );
+
+        assert!(world.unserializable_types().is_empty());
+        let loaded = World::from_yaml(&world.to_yaml().unwrap()).unwrap();
+        assert!(world.diff(&loaded).is_empty(), "{}", world.diff(&loaded));
+        let owned_by = loaded.resource::<Relation<OwnedBy>>();
+        assert_eq!(owned_by.on_target_killed, OnTargetKilled::KillSource);
+        assert_eq!(owned_by.sources(player), [gem, sword]);
+        drop(owned_by);
+
+        // The relations are cleaned up in the loaded world.
+        loaded.resource_mut::<Entities>().kill(player);
+        loaded.maintain();
+        assert!(!loaded.resource::<Entities>().is_alive(sword));
+        assert!(loaded.resource::<Relation<OwnedBy>>().is_empty());
+
+        // Changes to the relations show up in the diff.
+        let snapshot = world.clone();
+        world.resource_mut::<Relation<OwnedBy>>().remove(gem);
+        assert!(!snapshot.diff(&world).is_empty());
+    }
+}
@@ -1,6 +1,13 @@
 //! World resource storage.
 
-use std::{fmt::Debug, marker::PhantomData, sync::Arc};
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use once_map::OnceMap;
 
@@ -13,9 +20,15 @@ pub type AtomicUntypedResource = Arc<UntypedResource>;
 ///
 /// This is fundamentally a [`Arc<AtomicCell<Option<SchemaBox>>>`] and thus represents
 /// a cell that may or may not contain a resource of it's schema.
+///
+/// The resource data is copy-on-write: when the [`UntypedResources`] are cloned, the new cells
+/// share the data with the original ones, and it is only copied the first time that one of them
+/// is borrowed mutably.
 pub struct UntypedResource {
-    cell: AtomicCell<Option<SchemaBox>>,
+    cell: AtomicCell<Arc<Option<SchemaBox>>>,
     schema: &'static Schema,
+    /// The number of times that the resource has been changed.
+    change_count: AtomicU64,
 }
 
 impl std::fmt::Debug for UntypedResource {
@@ -28,8 +41,9 @@ impl UntypedResource {
     /// Initialize a new, empty [`UntypedResource`].
     pub fn empty(schema: &'static Schema) -> Self {
         Self {
-            cell: AtomicCell::new(None),
+            cell: AtomicCell::new(Arc::new(None)),
             schema,
+            change_count: AtomicU64::new(0),
         }
     }
 
@@ -37,7 +51,8 @@ impl UntypedResource {
     pub fn new(resource: SchemaBox) -> Self {
         Self {
             schema: resource.schema(),
-            cell: AtomicCell::new(Some(resource)),
+            cell: AtomicCell::new(Arc::new(Some(resource))),
+            change_count: AtomicU64::new(0),
         }
     }
 
@@ -45,15 +60,27 @@ impl UntypedResource {
     /// value for the schema.
     pub fn from_default(schema: &'static Schema) -> Self {
         Self {
-            cell: AtomicCell::new(Some(SchemaBox::default(schema))),
+            cell: AtomicCell::new(Arc::new(Some(SchemaBox::default(schema)))),
             schema,
+            change_count: AtomicU64::new(0),
         }
     }
 
     /// Clone the inner data, creating a new copy instead of returning another handle the the same
     /// data, as the normal `clone()` implementation does.
     pub fn clone_data(&self) -> Option<SchemaBox> {
-        (*self.cell.borrow()).clone()
+        (*self.borrow()).clone()
+    }
+
+    /// Create a new cell that shares the resource data with this one.
+    ///
+    /// The data will be copied the first time that either cell is borrowed mutably.
+    fn clone_cow(&self) -> Self {
+        Self {
+            cell: AtomicCell::new(self.cell.borrow().clone()),
+            schema: self.schema,
+            change_count: AtomicU64::new(self.change_count()),
+        }
     }
 
     /// Insert resource data into the cell, returning the previous data.
@@ -62,27 +89,53 @@ impl UntypedResource {
     pub fn insert(&self, data: SchemaBox) -> Result<Option<SchemaBox>, SchemaMismatchError> {
         self.schema.ensure_match(data.schema())?;
         let mut data = Some(data);
-        std::mem::swap(&mut data, &mut *self.cell.borrow_mut());
+        std::mem::swap(&mut data, &mut *self.borrow_mut());
         Ok(data)
     }
 
     /// Remove the resource data, returning what was stored in it.
     pub fn remove(&self) -> Option<SchemaBox> {
         let mut data = None;
-        std::mem::swap(&mut data, &mut *self.cell.borrow_mut());
+        std::mem::swap(&mut data, &mut *self.borrow_mut());
         data
     }
 
     /// Borrow the resource.
     #[track_caller]
     pub fn borrow(&self) -> Ref<Option<SchemaBox>> {
-        self.cell.borrow()
+        Ref::map(self.cell.borrow(), |data| &**data)
     }
 
     /// Mutably borrow the resource.
+    ///
+    /// This counts as a change to the resource, see [`change_count()`][Self::change_count].
+    ///
+    /// If the resource data is shared with a clone of this cell, it will be copied first.
     #[track_caller]
     pub fn borrow_mut(&self) -> RefMut<Option<SchemaBox>> {
-        self.cell.borrow_mut()
+        let borrow = self.borrow_mut_untracked();
+        self.mark_changed();
+        borrow
+    }
+
+    /// Mutably borrow the resource without marking it as changed.
+    #[track_caller]
+    pub(crate) fn borrow_mut_untracked(&self) -> RefMut<Option<SchemaBox>> {
+        RefMut::map(self.cell.borrow_mut(), Arc::make_mut)
+    }
+
+    /// Get the number of times that the resource has been changed.
+    ///
+    /// The resource is considered changed every time it is inserted, removed, or mutably
+    /// borrowed. The [`ResMut`] and [`ResMutInit`] system parameters only count as a change when
+    /// they are actually dereferenced mutably.
+    pub fn change_count(&self) -> u64 {
+        self.change_count.load(Ordering::Relaxed)
+    }
+
+    /// Mark the resource as changed, incrementing its [`change_count()`][Self::change_count].
+    pub fn mark_changed(&self) {
+        self.change_count.fetch_add(1, Ordering::Relaxed);
     }
 
     /// Get the schema of the resource.
@@ -99,7 +152,7 @@ impl UntypedResource {
 /// should use [`Resources`] instead.
 #[derive(Default)]
 pub struct UntypedResources {
-    resources: OnceMap<SchemaId, AtomicUntypedResource>,
+    pub(crate) resources: OnceMap<SchemaId, AtomicUntypedResource>,
     shared_resources: OnceMap<SchemaId, Box<()>>,
 }
 
@@ -114,16 +167,8 @@ impl Clone for UntypedResources {
             let is_shared = self.shared_resources.contains_key(&schema.id());
 
             if !is_shared {
-                let resource = resource_cell.clone_data();
-                new_resources.map_insert(
-                    schema.id(),
-                    |_| Arc::new(UntypedResource::empty(schema)),
-                    |_, cell| {
-                        if let Some(resource) = resource {
-                            cell.insert(resource).unwrap();
-                        }
-                    },
-                );
+                // Share the data with the new cell, it will be copied when it is first modified.
+                new_resources.insert(schema.id(), |_| Arc::new(resource_cell.clone_cow()));
             } else {
                 new_shared_resources.insert(schema.id(), |_| Box::new(()));
                 new_resources.insert(schema.id(), |_| resource_cell.clone());
@@ -229,7 +274,7 @@ impl Resources {
     ///
     /// See [get()][Self::get]
     pub fn contains<T: HasSchema>(&self) -> bool {
-        self.untyped.resources.contains_key(&T::schema().id())
+        self.untyped.contains(T::schema().id())
     }
 
     /// Remove a resource from the store, if it is present.
@@ -302,7 +347,6 @@ impl<T: HasSchema + Debug> Debug for AtomicResource<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("AtomicResource(")?;
         self.untyped
-            .cell
             .borrow()
             .as_ref()
             .map(|x| x.cast_ref::<T>())
@@ -383,6 +427,23 @@ impl<T: HasSchema> AtomicResource<T> {
         }
     }
 
+    /// Lock the resource for read-writing, without marking it as changed.
+    pub(crate) fn borrow_mut_untracked(&self) -> Option<RefMut<T>> {
+        let borrow = self.untyped.borrow_mut_untracked();
+        if borrow.is_some() {
+            Some(RefMut::map(borrow, |r| unsafe {
+                r.as_mut().unwrap().as_mut().cast_into_mut_unchecked()
+            }))
+        } else {
+            None
+        }
+    }
+
+    /// Get the untyped resource cell.
+    pub fn untyped(&self) -> &UntypedResource {
+        &self.untyped
+    }
+
     /// Convert into an untyped resource.
     pub fn into_untyped(self) -> AtomicUntypedResource {
         self.untyped
@@ -392,9 +453,13 @@ impl<T: HasSchema> AtomicResource<T> {
 impl<T: HasSchema + FromWorld> AtomicResource<T> {
     /// Initialize the resource using it's [`FromWorld`] implementation, if it is not present.
     pub fn init(&self, world: &World) {
-        let mut borrow = self.untyped.borrow_mut();
-        if unlikely(borrow.is_none()) {
-            *borrow = Some(SchemaBox::new(T::from_world(world)))
+        // Check with an immutable borrow first, so that we don't copy shared data needlessly.
+        if unlikely(self.untyped.borrow().is_none()) {
+            let mut borrow = self.untyped.borrow_mut_untracked();
+            if borrow.is_none() {
+                *borrow = Some(SchemaBox::new(T::from_world(world)));
+                self.untyped.mark_changed();
+            }
         }
     }
 
@@ -0,0 +1,388 @@
+//! Introspection of the systems in a [`SystemStages`] collection.
+//!
+//! [`SystemStages::schedule_info()`] lists the startup systems, the systems that run on the
+//! transitions of the states, and the systems of every stage, in the order that they run in, along
+//! with the data that they access and their run conditions. This makes it possible to review how
+//! the plugins of a session composed its schedule:
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! # #[derive(HasSchema, Clone, Default)]
+//! # #[repr(C)]
+//! # struct Pos(f32);
+//! fn movement(mut positions: CompMut<Pos>) {}
+//!
+//! let mut stages = SystemStages::with_core_stages();
+//! stages.add_system_to_stage(Update, movement);
+//!
+//! let schedule = stages.schedule_info();
+//! // Print the schedule as plain text.
+//! println!("{schedule}");
+//! // Or export it as a Graphviz graph, that can be rendered with `dot -Tsvg`.
+//! let dot = schedule.to_dot();
+//! ```
+
+use std::fmt::{self, Write};
+
+use crate::prelude::*;
+
+/// The systems of a [`SystemStages`] collection, returned by
+/// [`SystemStages::schedule_info()`].
+///
+/// The [`Display`][fmt::Display] implementation renders the schedule as plain text, and
+/// [`to_dot()`][Self::to_dot] as a Graphviz graph.
+#[derive(Clone, Debug, Default)]
+pub struct ScheduleInfo {
+    /// The systems that run once, before the stages.
+    pub startup_systems: Vec<SystemInfo>,
+    /// The states, with the systems that run on their transitions, before the stages.
+    pub states: Vec<StateInfo>,
+    /// The stages, in the order that they run in.
+    pub stages: Vec<StageInfo>,
+}
+
+/// A state added with [`SystemStages::add_state()`], in a [`ScheduleInfo`].
+#[derive(Clone, Debug)]
+pub struct StateInfo {
+    /// The name of the state type.
+    pub name: String,
+    /// The systems that run when exiting a state, with the state that they are for.
+    pub on_exit: Vec<(String, SystemInfo)>,
+    /// The systems that run when entering a state, with the state that they are for.
+    pub on_enter: Vec<(String, SystemInfo)>,
+}
+
+/// A stage in a [`ScheduleInfo`].
+#[derive(Clone, Debug)]
+pub struct StageInfo {
+    /// The unique identifier of the stage.
+    pub id: Ulid,
+    /// The name of the stage.
+    pub name: String,
+    /// The run conditions of the stage, which run before its systems.
+    pub run_conditions: Vec<SystemInfo>,
+    /// The systems of the stage, in the order that they run in, followed by the exclusive systems
+    /// that run at the end of the stage.
+    pub systems: Vec<SystemInfo>,
+}
+
+/// A system in a [`ScheduleInfo`].
+#[derive(Clone, Debug)]
+pub struct SystemInfo {
+    /// The name of the system.
+    pub name: &'static str,
+    /// Whether or not this is an [`ExclusiveSystem`].
+    pub exclusive: bool,
+    /// The data that the system accesses.
+    ///
+    /// Exclusive systems access the whole world.
+    pub access: SystemAccess,
+    /// The labels and ordering constraints of the system.
+    pub ordering: SystemOrdering,
+    /// The names of the run conditions of the system.
+    ///
+    /// Their access is included in the [`access`][Self::access] of the system.
+    pub run_conditions: Vec<&'static str>,
+}
+
+impl SystemInfo {
+    pub(crate) fn new<Out>(system: &StaticSystem<(), Out>) -> Self {
+        Self {
+            name: system.name,
+            exclusive: false,
+            access: system.access.clone(),
+            ordering: system.ordering.clone(),
+            run_conditions: system.run_conditions.clone(),
+        }
+    }
+
+    fn exclusive(system: &ExclusiveSystem) -> Self {
+        Self {
+            name: system.name,
+            exclusive: true,
+            access: SystemAccess {
+                world: true,
+                ..default()
+            },
+            ordering: system.ordering.clone(),
+            run_conditions: system.run_conditions.clone(),
+        }
+    }
+
+    /// The lists of resources and components that the system accesses, with a description of each
+    /// list, skipping the empty ones.
+    fn access_lists(&self) -> impl Iterator<Item = (&'static str, String)> + '_ {
+        let access = &self.access;
+        let world = access
+            .world
+            .then(|| ("accesses", "the whole world".to_string()));
+        let lists = [
+            ("reads resources", &access.resource_reads),
+            ("writes resources", &access.resource_writes),
+            ("reads components", &access.component_reads),
+            ("writes components", &access.component_writes),
+        ]
+        .into_iter()
+        .filter(|(_, schemas)| !schemas.is_empty())
+        .map(|(kind, schemas)| {
+            let names = schemas.iter().map(|schema| schema.name.as_str());
+            (kind, names.collect::<Vec<_>>().join(", "))
+        });
+        world.into_iter().chain(lists)
+    }
+
+    /// The labels and the run conditions of the system, with a description of each list, skipping
+    /// the empty ones.
+    fn config_lists(&self) -> impl Iterator<Item = (&'static str, String)> + '_ {
+        let labels = self.ordering.labels.iter().map(|label| label.as_str());
+        [
+            ("labels", labels.collect::<Vec<_>>()),
+            ("run if", self.run_conditions.clone()),
+        ]
+        .into_iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(kind, names)| (kind, names.join(", ")))
+    }
+}
+
+impl StateInfo {
+    /// The systems of the state, in the order that they run in, with a note saying which
+    /// transition they run on.
+    fn systems(&self) -> impl Iterator<Item = (&SystemInfo, String)> {
+        let on_exit = self
+            .on_exit
+            .iter()
+            .map(|(state, system)| (system, format!("on exit {state}")));
+        let on_enter = self
+            .on_enter
+            .iter()
+            .map(|(state, system)| (system, format!("on enter {state}")));
+        on_exit.chain(on_enter)
+    }
+}
+
+impl StageInfo {
+    /// The run conditions and the systems of the stage, in the order that they run in, with a
+    /// note for the run conditions and the exclusive systems.
+    fn systems(&self) -> impl Iterator<Item = (&SystemInfo, String)> {
+        let run_conditions = self
+            .run_conditions
+            .iter()
+            .map(|system| (system, "run condition".to_string()));
+        let systems = self.systems.iter().map(|system| {
+            let note = if system.exclusive { "exclusive" } else { "" };
+            (system, note.to_string())
+        });
+        run_conditions.chain(systems)
+    }
+}
+
+impl SystemStages {
+    /// List the startup systems, the systems of the states and the systems of every stage, with
+    /// their access and their run conditions.
+    ///
+    /// Stages that don't expose their systems through [`SystemStage::systems()`] are listed
+    /// without systems.
+    pub fn schedule_info(&self) -> ScheduleInfo {
+        ScheduleInfo {
+            startup_systems: self.startup_systems.iter().map(SystemInfo::new).collect(),
+            states: self.states.iter().map(|state| state.info()).collect(),
+            stages: self
+                .stages
+                .iter()
+                .map(|stage| {
+                    let systems = stage.systems().iter().map(SystemInfo::new);
+                    let exclusive_systems = self
+                        .exclusive_systems
+                        .get(&stage.id())
+                        .into_iter()
+                        .flatten()
+                        .map(SystemInfo::exclusive);
+                    StageInfo {
+                        id: stage.id(),
+                        name: stage.name(),
+                        run_conditions: stage
+                            .run_conditions()
+                            .iter()
+                            .map(SystemInfo::new)
+                            .collect(),
+                        systems: systems.chain(exclusive_systems).collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ScheduleInfo {
+    /// Render the schedule as a Graphviz DOT graph.
+    ///
+    /// The startup systems, every state and every stage are clusters containing their systems,
+    /// and the edges follow the order that the systems run in.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        self.write_dot(&mut dot).unwrap();
+        dot
+    }
+
+    /// The startup systems, the states and the stages, in the order that they run in, with their
+    /// systems and the notes for the systems.
+    fn sections(&self) -> Vec<(String, Vec<(&SystemInfo, String)>)> {
+        let startup = self
+            .startup_systems
+            .iter()
+            .map(|system| (system, String::new()));
+        let states = self
+            .states
+            .iter()
+            .map(|state| (format!("State {}", state.name), state.systems().collect()));
+        let stages = self.stages.iter().map(|stage| {
+            (
+                format!("{} ( {} )", stage.name, stage.id),
+                stage.systems().collect(),
+            )
+        });
+        std::iter::once(("Startup".to_string(), startup.collect()))
+            .chain(states)
+            .chain(stages)
+            .collect()
+    }
+
+    fn write_dot(&self, dot: &mut String) -> fmt::Result {
+        writeln!(dot, "digraph schedule {{")?;
+        writeln!(dot, "    node [shape=box];")?;
+
+        let mut previous = None;
+        for (i, (name, systems)) in self.sections().into_iter().enumerate() {
+            let stage_node = format!("stage_{i}");
+            writeln!(dot, "    subgraph cluster_{i} {{")?;
+            writeln!(dot, "        label=\"{}\";", escape_dot(&name))?;
+            writeln!(
+                dot,
+                "        {stage_node} [label=\"{}\", shape=ellipse];",
+                escape_dot(&name)
+            )?;
+            if let Some(previous) = previous.replace(stage_node.clone()) {
+                writeln!(dot, "        {previous} -> {stage_node};")?;
+            }
+
+            for (j, (system, note)) in systems.into_iter().enumerate() {
+                let mut label = escape_dot(system.name);
+                if !note.is_empty() {
+                    write!(label, "\\n({})", escape_dot(&note))?;
+                }
+                for (kind, names) in system.access_lists().chain(system.config_lists()) {
+                    write!(label, "\\n{kind}: {}", escape_dot(&names))?;
+                }
+
+                let node = format!("system_{i}_{j}");
+                writeln!(dot, "        {node} [label=\"{label}\"];")?;
+                writeln!(dot, "        {} -> {node};", previous.as_ref().unwrap())?;
+                previous = Some(node);
+            }
+            writeln!(dot, "    }}")?;
+        }
+
+        writeln!(dot, "}}")
+    }
+}
+
+impl fmt::Display for ScheduleInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, systems) in self.sections() {
+            writeln!(f, "{name}")?;
+            for (system, note) in systems {
+                if note.is_empty() {
+                    writeln!(f, "  - {}", system.name)?;
+                } else {
+                    writeln!(f, "  - {} ({note})", system.name)?;
+                }
+                for (kind, names) in system.access_lists().chain(system.config_lists()) {
+                    writeln!(f, "      {kind}: {names}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Escape a string so that it can be put in a quoted DOT string.
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Pos;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Vel;
+
+    #[derive(HasSchema, Clone, Default, PartialEq, Eq)]
+    #[repr(u8)]
+    enum GameState {
+        #[default]
+        Menu,
+        Playing,
+    }
+
+    fn movement(_pos: CompMut<Pos>, _vel: Comp<Vel>) {}
+    fn setup() {}
+    fn spawn_level(_world: &World) {}
+    fn is_running() -> bool {
+        true
+    }
+
+    #[test]
+    fn schedule_info() {
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_startup_system(setup)
+            .add_system_to_stage(Update, movement.label("movement").run_if(is_running))
+            .add_exclusive_system_to_stage(Update, |_: &mut World| ())
+            .add_stage_run_condition(Update, is_running)
+            .add_state(GameState::Menu)
+            .add_state_system(OnEnter(GameState::Playing), spawn_level);
+
+        let schedule = stages.schedule_info();
+        assert_eq!(schedule.startup_systems.len(), 1);
+        assert_eq!(schedule.states.len(), 1);
+        assert_eq!(schedule.states[0].name, "GameState");
+        assert!(schedule.states[0].on_exit.is_empty());
+        assert_eq!(schedule.states[0].on_enter.len(), 1);
+        assert!(schedule.states[0].on_enter[0].1.access.world);
+        assert_eq!(schedule.stages.len(), 5);
+        let update = &schedule.stages[2];
+        assert_eq!(update.name, "Update");
+        assert_eq!(update.run_conditions.len(), 1);
+        assert!(update.run_conditions[0].name.ends_with("is_running"));
+        assert_eq!(update.systems.len(), 2);
+        assert!(update.systems[0].name.ends_with("movement"));
+        assert!(update.systems[0].run_conditions[0].ends_with("is_running"));
+        assert!(update.systems[1].exclusive);
+
+        let text = schedule.to_string();
+        assert!(text.contains("      reads components: Vel\n      writes components: Pos\n"));
+        assert!(text.contains("      labels: movement\n"));
+        assert!(text.contains("is_running\n"));
+        assert!(text.contains("is_running (run condition)\n"));
+        assert!(text.contains("State GameState\n"));
+        assert!(text.contains(
+            "spawn_level (on enter GameState::Playing)\n      accesses: the whole world\n"
+        ));
+        assert!(text.contains("(exclusive)\n      accesses: the whole world\n"));
+
+        let dot = schedule.to_dot();
+        assert!(dot.starts_with("digraph schedule {"));
+        assert!(dot.contains("stage_3 -> stage_4;"));
+        assert!(dot.contains("stage_1 -> system_1_0;"));
+        assert!(dot.contains("stage_4 -> system_4_0;"));
+        assert!(dot.contains("system_4_1 -> system_4_2;"));
+        assert!(dot.contains("system_4_2 -> stage_5;"));
+        assert!(dot.contains("(run condition)"));
+        assert!(dot.contains("\\nrun if: "));
+    }
+}
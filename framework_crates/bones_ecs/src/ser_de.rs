@@ -0,0 +1,418 @@
+//! Serializing and deserializing [`World`]s.
+//!
+//! [`World`] implements [`Serialize`] and [`Deserialize`], so it can be saved with any serde
+//! format. [`World::to_yaml()`] and [`World::to_bytes()`] are provided for a human-readable format
+//! and a compact binary format ([MessagePack](https://msgpack.org)).
+//!
+//! The serialized world contains:
+//!
+//! - the generation of every entity index that has been used, so that entities created after
+//!   loading the world get the same IDs as they would have in the original world,
+//! - the alive entities, along with their components,
+//! - and the resources, except for [`Entities`].
+//!
+//! Components and resources are serialized with [`SchemaSerializer`] and keyed by the
+//! [`full_name`][SchemaData::full_name] of their schema. When deserializing, the schemas are looked up
+//! by name in the [`SCHEMA_REGISTRY`], so they must have been registered, for example by calling
+//! [`HasSchema::schema()`], before the world is loaded.
+//!
+//! Types that contain opaque values can't be serialized, and are skipped. Use
+//! [`World::unserializable_types()`] to find out which ones. With the `tracing` feature, a warning
+//! listing them is logged when they are skipped.
+//!
+//! Entities that have been killed, but not yet cleaned up with [`World::maintain()`], are not
+//! included.
+
+use std::cell::RefCell;
+
+use serde::{
+    de::{Error, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::prelude::*;
+
+impl World {
+    /// Serialize the world to YAML.
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(self)
+    }
+
+    /// Deserialize a world from YAML.
+    pub fn from_yaml(yaml: &str) -> Result<World, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
+    }
+
+    /// Serialize the world to a compact binary format.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec(self)
+    }
+
+    /// Deserialize a world from the binary format created by [`to_bytes()`][Self::to_bytes].
+    pub fn from_bytes(bytes: &[u8]) -> Result<World, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+
+    /// Get the schemas of the components and resources in the world that can't be serialized,
+    /// and will be skipped when serializing the world.
+    ///
+    /// These are types that contain opaque values, sorted by name.
+    pub fn unserializable_types(&self) -> Vec<&'static Schema> {
+        let stores = self.components.components.read_only_view();
+        let resources = self.resources.untyped().resources.read_only_view();
+        let schemas = stores
+            .values()
+            .map(|store| store.borrow().schema())
+            .chain(
+                resources
+                    .values()
+                    .filter(|cell| cell.borrow().is_some())
+                    .map(|cell| cell.schema()),
+            )
+            .filter(|&schema| schema != Entities::schema() && !is_serializable(schema));
+        crate::diff::sorted_schemas(schemas)
+    }
+}
+
+impl Serialize for World {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[cfg(feature = "tracing")]
+        {
+            let skipped = self.unserializable_types();
+            if !skipped.is_empty() {
+                let names = skipped
+                    .iter()
+                    .map(|x| x.full_name.as_str())
+                    .collect::<Vec<_>>();
+                tracing::warn!(
+                    "Skipping types that contain opaque values while serializing the world: {}",
+                    names.join(", ")
+                );
+            }
+        }
+
+        let entities = self.resource::<Entities>();
+
+        let stores = self.components.components.read_only_view();
+        let mut stores = stores
+            .values()
+            .map(|store| store.borrow())
+            .filter(|store| is_serializable(store.schema()))
+            .collect::<Vec<_>>();
+        stores.sort_by_key(|store| store.schema().full_name);
+
+        let resources = self.resources.untyped().resources.read_only_view();
+        let mut resources = resources
+            .values()
+            .filter(|cell| cell.schema() != Entities::schema() && is_serializable(cell.schema()))
+            .map(|cell| cell.borrow())
+            .collect::<Vec<_>>();
+        resources.sort_by_key(|resource| resource.as_ref().map(|x| x.schema().full_name));
+
+        WorldSer {
+            generations: entities.generations(),
+            entities: entities
+                .iter_with_bitset(entities.bitset())
+                .map(|entity| EntitySer {
+                    entity,
+                    components: ValuesSer(
+                        stores
+                            .iter()
+                            .filter_map(|store| store.get_ref(entity))
+                            .collect(),
+                    ),
+                })
+                .collect(),
+            resources: ValuesSer(
+                resources
+                    .iter()
+                    .filter_map(|x| x.as_ref())
+                    .map(|x| x.as_ref())
+                    .collect(),
+            ),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for World {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = with_schemas_by_name(|| WorldDe::deserialize(deserializer))?;
+        let entities = Entities::from_generations(
+            &data.generations,
+            data.entities.iter().map(|x| x.entity),
+        )
+        .ok_or_else(|| D::Error::custom("The entities don't match the entity generations"))?;
+
+        let world = World::new();
+        world.resources.insert(entities);
+        for EntityDe { entity, components } in data.entities {
+            for component in components.0 {
+                let store = world.components.get_by_schema(component.schema());
+                store.borrow_mut().insert_box(entity, component);
+            }
+        }
+        for resource in data.resources.0 {
+            let cell = world.resources.untyped().get(resource.schema());
+            // The schema always matches, because we got the cell with the resource's schema.
+            cell.insert(resource).unwrap();
+        }
+
+        Ok(world)
+    }
+}
+
+/// Whether or not values with the given schema can be serialized with [`SchemaSerializer`].
+fn is_serializable(schema: &'static Schema) -> bool {
+    fn is_serializable(schema: &'static Schema, visited: &mut Vec<SchemaId>) -> bool {
+        // Recursive types have already been checked, or are being checked higher up.
+        if visited.contains(&schema.id()) {
+            return true;
+        }
+        visited.push(schema.id());
+
+        match &schema.kind {
+            SchemaKind::Struct(s) => s
+                .fields
+                .iter()
+                .all(|field| is_serializable(field.schema, visited)),
+            SchemaKind::Enum(e) => e
+                .variants
+                .iter()
+                .all(|variant| is_serializable(variant.schema, visited)),
+            SchemaKind::Vec(item) | SchemaKind::Box(item) => is_serializable(item, visited),
+            SchemaKind::Map { key, value } => {
+                is_serializable(key, visited) && is_serializable(value, visited)
+            }
+            // `Ustr`s are opaque, but `SchemaSerializer` handles them specially.
+            SchemaKind::Primitive(Primitive::Opaque { .. }) => schema == Ustr::schema(),
+            SchemaKind::Primitive(_) => true,
+        }
+    }
+    is_serializable(schema, &mut Vec::new())
+}
+
+thread_local! {
+    /// The registered schemas by full name, for the world being deserialized on the current thread.
+    static SCHEMAS_BY_NAME: RefCell<Option<HashMap<&'static str, &'static Schema>>> =
+        const { RefCell::new(None) };
+}
+
+/// Run a function that deserializes a world, looking up schemas in a map of the registered
+/// schemas by name, that is built once for the whole world.
+fn with_schemas_by_name<R>(f: impl FnOnce() -> R) -> R {
+    /// Restores the previous map when dropped, even if deserializing panics.
+    struct Restore(Option<HashMap<&'static str, &'static Schema>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCHEMAS_BY_NAME.with(|schemas| *schemas.borrow_mut() = self.0.take());
+        }
+    }
+    let schemas = SCHEMA_REGISTRY
+        .schemas
+        .iter()
+        .map(|schema| (schema.full_name.as_str(), schema))
+        .collect();
+    let _restore = Restore(SCHEMAS_BY_NAME.with(|x| x.replace(Some(schemas))));
+    f()
+}
+
+/// Find a registered schema by its full name.
+fn schema_by_name(name: &str) -> Option<&'static Schema> {
+    SCHEMAS_BY_NAME.with(|schemas| match &*schemas.borrow() {
+        Some(schemas) => schemas.get(name).copied(),
+        None => SCHEMA_REGISTRY
+            .schemas
+            .iter()
+            .find(|schema| schema.full_name.as_str() == name),
+    })
+}
+
+#[derive(Serialize)]
+struct WorldSer<'a> {
+    generations: &'a [u32],
+    entities: Vec<EntitySer<'a>>,
+    resources: ValuesSer<'a>,
+}
+
+#[derive(Serialize)]
+struct EntitySer<'a> {
+    entity: Entity,
+    components: ValuesSer<'a>,
+}
+
+/// Serializes values as a map from their schema's full name to the value.
+struct ValuesSer<'a>(Vec<SchemaRef<'a>>);
+
+impl Serialize for ValuesSer<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for value in &self.0 {
+            map.serialize_entry(value.schema().full_name.as_str(), &SchemaSerializer(*value))?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct WorldDe {
+    generations: Vec<u32>,
+    entities: Vec<EntityDe>,
+    resources: ValuesDe,
+}
+
+#[derive(Deserialize)]
+struct EntityDe {
+    entity: Entity,
+    components: ValuesDe,
+}
+
+/// Deserializes the map created by [`ValuesSer`].
+struct ValuesDe(Vec<SchemaBox>);
+
+impl<'de> Deserialize<'de> for ValuesDe {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(ValuesVisitor)
+    }
+}
+
+struct ValuesVisitor;
+
+impl<'de> Visitor<'de> for ValuesVisitor {
+    type Value = ValuesDe;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "a map from type names to values")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut values = Vec::new();
+        while let Some(name) = map.next_key::<String>()? {
+            let schema = schema_by_name(&name).ok_or_else(|| {
+                A::Error::custom(format!(
+                    "Unknown type `{name}`: its schema must be registered, for example by calling \
+                    `HasSchema::schema()`, before the world is deserialized"
+                ))
+            })?;
+            if schema.default_fn.is_none() {
+                return Err(A::Error::custom(format!(
+                    "Type `{name}` can't be deserialized because it has no default value"
+                )));
+            }
+            values.push(map.next_value_seed(SchemaDeserializer(schema))?);
+        }
+        Ok(ValuesDe(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Debug, Default, PartialEq)]
+    #[repr(C)]
+    struct Pos {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(HasSchema, Clone, Debug, Default, PartialEq)]
+    #[repr(C, u8)]
+    enum State {
+        #[default]
+        Idle,
+        Moving(f32),
+        Jumping {
+            height: f32,
+        },
+    }
+
+    #[derive(HasSchema, Clone, Debug, Default, PartialEq)]
+    #[repr(C)]
+    struct Score {
+        value: u32,
+        history: SVec<u32>,
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Opaque(#[allow(dead_code)] Vec<u32>);
+
+    #[test]
+    fn world_roundtrip() {
+        let world = World::new();
+        let [a, b, c] = world.run_system(
+            |mut entities: ResMut<Entities>,
+             mut pos: CompMut<Pos>,
+             mut state: CompMut<State>,
+             mut opaque: CompMut<Opaque>| {
+                let [a, b, c] = std::array::from_fn(|_| entities.create());
+                pos.insert(a, Pos { x: 1.0, y: 2.0 });
+                state.insert(a, State::Moving(0.5));
+                state.insert(b, State::Jumping { height: 3.0 });
+                state.insert(c, State::Idle);
+                opaque.insert(c, Opaque(vec![1]));
+                entities.kill(b);
+                [a, b, c]
+            },
+            (),
+        );
+        world.maintain();
+        world.resources.insert(Score {
+            value: 3,
+            history: [1, 2].into_iter().collect(),
+        });
+        world.resources.insert(Opaque(vec![2]));
+
+        assert_eq!(world.unserializable_types(), [Opaque::schema()]);
+
+        let yaml = world.to_yaml().unwrap();
+        let bytes = world.to_bytes().unwrap();
+        for loaded in [
+            World::from_yaml(&yaml).unwrap(),
+            World::from_bytes(&bytes).unwrap(),
+        ] {
+            // Only the opaque types are missing, and they can't be compared.
+            loaded.resources.insert(Opaque(vec![2]));
+            loaded
+                .components
+                .get::<Opaque>()
+                .borrow_mut()
+                .insert(c, Opaque(vec![1]));
+            assert_eq!(
+                world.diff(&loaded).to_string(),
+                format!(
+                    "Worlds are identical\n\
+                     {c:?} Opaque: not comparable\n\
+                     Resource Opaque: not comparable\n"
+                )
+            );
+
+            // New entities get the same IDs as in the original world.
+            let mut entities = loaded.resource_mut::<Entities>();
+            assert!(entities.is_alive(a) && !entities.is_alive(b) && entities.is_alive(c));
+            assert_eq!(
+                entities.create(),
+                world.resource_mut::<Entities>().clone().create()
+            );
+        }
+
+        let error = World::from_yaml(&yaml.replace("Score", "Unknown")).unwrap_err();
+        assert!(error.to_string().contains("Unknown type"), "{error}");
+    }
+}
@@ -2,7 +2,16 @@
 
 use std::collections::VecDeque;
 
-use crate::prelude::*;
+use bevy_tasks::{ComputeTaskPool, TaskPool};
+
+use crate::{
+    hooks::HookEventOrder,
+    prelude::*,
+    system::ChangeTicks,
+    timings::{
+        instrument_stage, instrument_system, is_timed, record_stage, record_system, with_timings,
+    },
+};
 
 /// Resource that is automatically added to the world while a system stage is being run
 /// that specifies the unique ID of the stage that being run.
@@ -19,13 +28,31 @@ pub struct SystemStages {
     pub has_started: bool,
     /// The systems that should run at startup.
     pub startup_systems: Vec<StaticSystem<(), ()>>,
+    /// The [`ExclusiveSystem`]s that run at the end of each stage, by stage ID.
+    pub exclusive_systems: HashMap<Ulid, Vec<ExclusiveSystem>>,
+    /// The states added with [`add_state()`][Self::add_state].
+    pub(crate) states: Vec<Box<dyn crate::state::StateMachine>>,
+    /// The timings of the systems and stages, recorded while this is [`Some`].
+    ///
+    /// See the [`timings`][crate::timings] module.
+    pub timings: Option<SystemTimings>,
 }
 impl std::fmt::Debug for SystemStages {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stages = self
+            .stages
+            .iter()
+            .map(|stage| stage.name())
+            .collect::<Vec<_>>();
+        let startup_systems = self
+            .startup_systems
+            .iter()
+            .map(|system| system.name)
+            .collect::<Vec<_>>();
         f.debug_struct("SystemStages")
-            // TODO: Add list of stages to the debug render for `SystemStages`.
-            // We can at least list the names of each stage for `SystemStages` debug
-            // implementation.
+            .field("stages", &stages)
+            .field("has_started", &self.has_started)
+            .field("startup_systems", &startup_systems)
             .finish()
     }
 }
@@ -39,32 +66,67 @@ impl Default for SystemStages {
 impl SystemStages {
     /// Execute the systems on the given `world`.
     pub fn run(&mut self, world: &mut World) {
+        let mut timings = self.timings.take();
+        with_timings(&mut timings, || self.run_stages(world));
+        self.timings = timings;
+    }
+
+    /// Execute the systems on the given `world`, recording their timings in the timings of the
+    /// current thread.
+    fn run_stages(&mut self, world: &mut World) {
         // If we haven't run our startup systems yet
         if !self.has_started {
             // Set the current stage resource
             world.insert_resource(CurrentSystemStage(Ulid(0)));
 
             // For each startup system
+            let timed = is_timed();
             for system in &mut self.startup_systems {
                 // Run the system
-                system.run(world, ());
+                let ((), duration) =
+                    instrument_system(system.name, timed, || system.run(world, ()));
+                record_system(system.name, duration);
             }
 
             // Don't run startup systems again
             self.has_started = true;
         }
 
+        // Apply state transitions
+        self.apply_state_transitions(world);
+
         // Run each stage
         for stage in &mut self.stages {
             // Set the current stage resource
             world.insert_resource(CurrentSystemStage(stage.id()));
 
-            // Run the stage
-            stage.run(world);
+            let name = stage.name();
+            let timed = is_timed();
+            let ((), duration) = instrument_stage(&name, timed, || {
+                // Run the stage
+                if !stage.run(world) {
+                    return;
+                }
+
+                // Run the exclusive systems at the end of the stage
+                if let Some(systems) = self.exclusive_systems.get_mut(&stage.id()) {
+                    for system in systems {
+                        let ((), duration) =
+                            instrument_system(system.name, timed, || system.run(world));
+                        record_system(system.name, duration);
+                    }
+                    drain_command_queue(world);
+                }
+            });
+            record_stage(&name, duration);
         }
 
-        // Cleanup killed entities
+        // Swap event buffers
+        world.update_events();
+
+        // Cleanup killed entities, and run the hooks for their removed components
         world.maintain();
+        drain_command_queue(world);
 
         // Remove the current system stage resource
         world.resources.remove::<CurrentSystemStage>();
@@ -82,6 +144,9 @@ impl SystemStages {
             ],
             has_started: false,
             startup_systems: default(),
+            exclusive_systems: default(),
+            states: Vec::new(),
+            timings: None,
         }
     }
 
@@ -99,6 +164,61 @@ impl SystemStages {
     where
         S: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
     {
+        self.get_stage_mut(label).add_system(system.system());
+
+        self
+    }
+
+    /// Add an [`ExclusiveSystem`] to the stage with the given label.
+    ///
+    /// Exclusive systems run after all of the other systems in the stage, once their [`Commands`]
+    /// have been applied, in the order given by their [`SystemOrdering`]. They are skipped along
+    /// with the other systems when the stage's run conditions aren't met.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no stage with the label, or if the ordering constraints of the exclusive
+    /// systems of the stage contain a cycle.
+    pub fn add_exclusive_system_to_stage<S>(
+        &mut self,
+        label: impl StageLabel,
+        system: S,
+    ) -> &mut Self
+    where
+        S: IntoExclusiveSystem,
+    {
+        let stage = self.get_stage_mut(label);
+        let (id, name) = (stage.id(), stage.name());
+        let systems = self.exclusive_systems.entry(id).or_default();
+        systems.push(system.exclusive_system());
+        sort_systems(&name, systems);
+
+        self
+    }
+
+    /// Add a [run condition][Condition] to the stage with the given label.
+    ///
+    /// None of the systems in the stage will run unless all of its conditions return `true`.
+    pub fn add_stage_run_condition<Args, C>(
+        &mut self,
+        label: impl StageLabel,
+        condition: C,
+    ) -> &mut Self
+    where
+        C: IntoSystem<Args, (), bool, Sys = StaticSystem<(), bool>>,
+    {
+        self.get_stage_mut(label)
+            .add_run_condition(condition.system());
+
+        self
+    }
+
+    /// Get the stage with the given label.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no stage with the label.
+    fn get_stage_mut(&mut self, label: impl StageLabel) -> &mut Box<dyn SystemStage> {
         let name = label.name();
         let id = label.id();
         let mut stage = None;
@@ -113,7 +233,21 @@ impl SystemStages {
             panic!("Stage with label `{}` ( {} ) doesn't exist.", name, id);
         };
 
-        stage.add_system(system.system());
+        stage
+    }
+
+    /// Replace the stage with the given label with a new stage.
+    ///
+    /// This can be used to opt in to running one of the core stages with a
+    /// [`ParallelSystemStage`]. Any systems already added to the old stage are dropped, so this
+    /// should be done before adding systems to it.
+    #[track_caller]
+    pub fn replace_stage<L: StageLabel, S: SystemStage + 'static>(
+        &mut self,
+        label: L,
+        stage: S,
+    ) -> &mut Self {
+        *self.get_stage_mut(label) = Box::new(stage);
 
         self
     }
@@ -160,10 +294,45 @@ pub trait SystemStage: Sync + Send {
     /// The human-readable name for the stage, used for error messages when something goes wrong.
     fn name(&self) -> String;
     /// Execute the systems on the given `world`.
-    fn run(&mut self, world: &World);
+    ///
+    /// Returns `false` if the run conditions of the stage skipped its systems, in which case its
+    /// exclusive systems are skipped too.
+    fn run(&mut self, world: &World) -> bool;
+
+    /// Get the systems in the stage, in the order that they run in, for introspection with
+    /// [`SystemStages::schedule_info()`].
+    ///
+    /// The default implementation returns no systems.
+    fn systems(&self) -> &[StaticSystem<(), ()>] {
+        &[]
+    }
+
+    /// Get the run conditions of the stage, for introspection with
+    /// [`SystemStages::schedule_info()`].
+    ///
+    /// The default implementation returns no conditions.
+    fn run_conditions(&self) -> &[StaticSystem<(), bool>] {
+        &[]
+    }
 
     /// Add a system to this stage.
     fn add_system(&mut self, system: StaticSystem<(), ()>);
+
+    /// Add a run condition to this stage.
+    ///
+    /// The stage must skip running its systems unless all of its run conditions return `true`.
+    ///
+    /// # Panics
+    ///
+    /// The default implementation panics, for stages that don't support run conditions.
+    fn add_run_condition(&mut self, condition: StaticSystem<(), bool>) {
+        panic!(
+            "Cannot add the run condition `{}` to the stage `{}`, because it doesn't support \
+            run conditions",
+            condition.name,
+            self.name()
+        );
+    }
 }
 
 /// A collection of systems that will be run in order.
@@ -174,8 +343,14 @@ pub struct SimpleSystemStage {
     pub name: String,
     /// The list of systems in the stage.
     ///
-    /// Each system will be run in the order that they are in in this list.
+    /// Each system will be run in the order that they are in in this list. Systems are sorted
+    /// according to their [`SystemOrdering`] when they are added, and systems without ordering
+    /// constraints between them keep the order that they were added in.
     pub systems: Vec<StaticSystem<(), ()>>,
+    /// The run conditions for the stage.
+    ///
+    /// The systems in the stage will only be run if all of these conditions return `true`.
+    pub conditions: Vec<StaticSystem<(), bool>>,
 }
 
 impl SimpleSystemStage {
@@ -185,8 +360,18 @@ impl SimpleSystemStage {
             id: label.id(),
             name: label.name(),
             systems: Default::default(),
+            conditions: Default::default(),
         }
     }
+
+    /// Only run the stage if the given [run condition][Condition] returns `true`.
+    pub fn run_if<Args, C>(mut self, condition: C) -> Self
+    where
+        C: IntoSystem<Args, (), bool, Sys = StaticSystem<(), bool>>,
+    {
+        self.add_run_condition(condition.system());
+        self
+    }
 }
 
 impl SystemStage for SimpleSystemStage {
@@ -198,23 +383,314 @@ impl SystemStage for SimpleSystemStage {
         self.name.clone()
     }
 
-    fn run(&mut self, world: &World) {
+    fn run(&mut self, world: &World) -> bool {
+        // Skip the stage if any of the run conditions aren't met
+        if !self.conditions.iter_mut().all(|cond| cond.run(world, ())) {
+            return false;
+        }
+
         // Run the systems
+        let timed = is_timed();
         for system in &mut self.systems {
-            system.run(world, ());
+            let ((), duration) = instrument_system(system.name, timed, || system.run(world, ()));
+            record_system(system.name, duration);
         }
 
-        // Drain the command queue
-        let queue = world.resources.get_mut::<CommandQueue>();
-        if let Some(mut command_queue) = queue {
-            for mut system in command_queue.queue.drain(..) {
-                system.run(world, ());
+        drain_command_queue(world);
+        true
+    }
+
+    fn systems(&self) -> &[StaticSystem<(), ()>] {
+        &self.systems
+    }
+
+    fn run_conditions(&self) -> &[StaticSystem<(), bool>] {
+        &self.conditions
+    }
+
+    fn add_system(&mut self, system: StaticSystem<(), ()>) {
+        self.systems.push(system);
+        sort_systems(&self.name, &mut self.systems);
+    }
+
+    fn add_run_condition(&mut self, condition: StaticSystem<(), bool>) {
+        self.conditions.push(condition);
+    }
+}
+
+/// A collection of systems that may be run in parallel on the [`ComputeTaskPool`].
+///
+/// Systems are split into batches based on their [`SystemAccess`] and [`SystemOrdering`]. A
+/// system is only put in a later batch than another system if they have conflicting access or
+/// an ordering constraint, and in that case it always runs after it, just like it would in a
+/// [`SimpleSystemStage`]. This makes the results the same as running the systems in order, which
+/// is required for determinism.
+///
+/// Systems that use parameters that don't report their access, such as [`&World`][World], are
+/// never run in parallel with other systems.
+pub struct ParallelSystemStage {
+    /// The unique identifier for the stage.
+    pub id: Ulid,
+    /// The human-readable name for the stage, used for error messages when something goes wrong.
+    pub name: String,
+    /// The list of systems in the stage, sorted the same way as in a [`SimpleSystemStage`].
+    systems: Vec<StaticSystem<(), ()>>,
+    /// The batches of systems that may run in parallel, as indexes into `systems`.
+    batches: Vec<Vec<usize>>,
+    /// The run conditions for the stage.
+    ///
+    /// The systems in the stage will only be run if all of these conditions return `true`.
+    pub conditions: Vec<StaticSystem<(), bool>>,
+}
+
+impl ParallelSystemStage {
+    /// Create a new, empty stage, for the given label.
+    pub fn new<L: StageLabel>(label: L) -> Self {
+        Self {
+            id: label.id(),
+            name: label.name(),
+            systems: Default::default(),
+            batches: Default::default(),
+            conditions: Default::default(),
+        }
+    }
+
+    /// Only run the stage if the given [run condition][Condition] returns `true`.
+    pub fn run_if<Args, C>(mut self, condition: C) -> Self
+    where
+        C: IntoSystem<Args, (), bool, Sys = StaticSystem<(), bool>>,
+    {
+        self.add_run_condition(condition.system());
+        self
+    }
+
+    /// Get the systems in the stage, in the order that they would run in sequentially.
+    pub fn systems(&self) -> &[StaticSystem<(), ()>] {
+        &self.systems
+    }
+
+    /// Get the batches of systems that will be run in parallel, as indexes into
+    /// [`systems()`][Self::systems].
+    pub fn batches(&self) -> &[Vec<usize>] {
+        &self.batches
+    }
+
+    /// Split the systems into batches that may be run in parallel.
+    fn update_batches(&mut self) {
+        let mut system_batches = Vec::<usize>::with_capacity(self.systems.len());
+        self.batches.clear();
+
+        for (i, system) in self.systems.iter().enumerate() {
+            // Put the system in the batch after the last system that it must run after.
+            let batch = self.systems[..i]
+                .iter()
+                .zip(&system_batches)
+                .filter(|(other, _)| {
+                    !other.access.is_compatible(&system.access)
+                        || other.ordering.runs_before(&system.ordering)
+                })
+                .map(|(_, &batch)| batch + 1)
+                .max()
+                .unwrap_or(0);
+
+            system_batches.push(batch);
+            if batch == self.batches.len() {
+                self.batches.push(Vec::new());
             }
+            self.batches[batch].push(i);
         }
     }
+}
+
+impl SystemStage for ParallelSystemStage {
+    fn id(&self) -> Ulid {
+        self.id
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn run(&mut self, world: &World) -> bool {
+        // Skip the stage if any of the run conditions aren't met
+        if !self.conditions.iter_mut().all(|cond| cond.run(world, ())) {
+            return false;
+        }
+
+        // Run each batch of systems
+        let timed = is_timed();
+        let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+        for batch in &self.batches {
+            if let [i] = batch[..] {
+                let system = &mut self.systems[i];
+                let ((), duration) = instrument_system(system.name, timed, || {
+                    HookEventOrder::with_system_position(i, || system.run(world, ()))
+                });
+                record_system(system.name, duration);
+                continue;
+            }
+
+            // The systems of the batch share a tick, so that it doesn't depend on the order that
+            // they start in. The timings are recorded once the whole batch has run, because they
+            // are stored on the thread that runs the stage.
+            let tick = world.components.ticks.next();
+            let durations = task_pool.scope(|scope| {
+                for (i, system) in self
+                    .systems
+                    .iter_mut()
+                    .enumerate()
+                    .filter(|(i, _)| batch.contains(i))
+                {
+                    scope.spawn(async move {
+                        let name = system.name;
+                        let ((), duration) = instrument_system(name, timed, || {
+                            ChangeTicks::batch(tick, || {
+                                HookEventOrder::with_system_position(i, || system.run(world, ()))
+                            })
+                        });
+                        (name, duration)
+                    });
+                }
+            });
+            for (name, duration) in durations {
+                record_system(name, duration);
+            }
+        }
+
+        drain_command_queue(world);
+        true
+    }
+
+    fn systems(&self) -> &[StaticSystem<(), ()>] {
+        &self.systems
+    }
+
+    fn run_conditions(&self) -> &[StaticSystem<(), bool>] {
+        &self.conditions
+    }
 
     fn add_system(&mut self, system: StaticSystem<(), ()>) {
         self.systems.push(system);
+        sort_systems(&self.name, &mut self.systems);
+        self.update_batches();
+    }
+
+    fn add_run_condition(&mut self, condition: StaticSystem<(), bool>) {
+        self.conditions.push(condition);
+    }
+}
+
+/// Run the [component hooks][crate::hooks] and all of the systems in the [`CommandQueue`], until
+/// there are no hooks or commands left.
+///
+/// The [reserved][Commands::spawn] entities are flushed first, so that commands can add
+/// components to them.
+pub(crate) fn drain_command_queue(world: &World) {
+    loop {
+        let ran_hooks = crate::hooks::run_hooks(world);
+        let commands = match world.resources.get_mut::<CommandQueue>() {
+            Some(mut command_queue) => std::mem::take(&mut command_queue.queue),
+            None => default(),
+        };
+        if commands.is_empty() && !ran_hooks {
+            break;
+        }
+
+        world.resource_mut::<Entities>().flush();
+        // The queue isn't borrowed while the commands run, so that they can queue more commands.
+        for mut system in commands {
+            ChangeTicks::one_shot(|| system.run(world, ()));
+        }
+    }
+}
+
+/// Sort the systems so that all of their [`SystemOrdering`] constraints are met.
+///
+/// When more than one system is ready to run, the one that comes first in the current list is
+/// picked, so the result is deterministic and keeps the existing order where possible.
+///
+/// # Panics
+///
+/// Panics if the ordering constraints contain a cycle.
+fn sort_systems<S: OrderedSystem>(stage_name: &str, systems: &mut Vec<S>) {
+    let count = systems.len();
+
+    // Build the list of systems that must run before each system
+    let dependencies = (0..count)
+        .map(|i| {
+            (0..count)
+                .filter(|&j| i != j && systems[j].ordering().runs_before(systems[i].ordering()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut order = Vec::with_capacity(count);
+    let mut sorted = vec![false; count];
+    while order.len() < count {
+        let next =
+            (0..count).find(|&i| !sorted[i] && dependencies[i].iter().all(|&dep| sorted[dep]));
+
+        let Some(next) = next else {
+            // Walk backwards through the unsorted dependencies until we find a system that we
+            // have already visited, which gives us a cycle.
+            let mut path = Vec::new();
+            let mut current = (0..count).find(|&i| !sorted[i]).unwrap();
+            while !path.contains(&current) {
+                path.push(current);
+                current = *dependencies[current]
+                    .iter()
+                    .find(|&&dep| !sorted[dep])
+                    .unwrap();
+            }
+            let cycle_start = path.iter().position(|&i| i == current).unwrap();
+            let cycle = path[cycle_start..]
+                .iter()
+                .rev()
+                .map(|&i| format!("`{}`", systems[i].name()))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            panic!(
+                "Cycle detected in the system ordering for stage `{}`: `{}` -> {cycle}",
+                stage_name,
+                systems[current].name()
+            );
+        };
+
+        sorted[next] = true;
+        order.push(next);
+    }
+
+    let mut unsorted = std::mem::take(systems)
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    *systems = order
+        .into_iter()
+        .map(|i| unsorted[i].take().unwrap())
+        .collect();
+}
+
+/// A system that can be sorted with [`sort_systems()`].
+trait OrderedSystem {
+    fn name(&self) -> &str;
+    fn ordering(&self) -> &SystemOrdering;
+}
+
+impl OrderedSystem for StaticSystem<(), ()> {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn ordering(&self) -> &SystemOrdering {
+        &self.ordering
+    }
+}
+
+impl OrderedSystem for ExclusiveSystem {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn ordering(&self) -> &SystemOrdering {
+        &self.ordering
     }
 }
 
@@ -259,7 +735,7 @@ impl StageLabel for CoreStage {
 
 /// A resource containing the [`Commands`] command queue.
 ///
-/// You can use [`Commands`] as a [`SystemParam`] as a shortcut to [`ResMut<CommandQueue>`].
+/// Use the [`Commands`] [`SystemParam`] to add commands to the queue.
 #[derive(HasSchema, Default)]
 pub struct CommandQueue {
     /// The system queue that will be run at the end of the stage
@@ -291,12 +767,82 @@ impl CommandQueue {
     }
 }
 
-/// A [`SystemParam`] that can be used to schedule systems that will be run at the end of the
-/// current [`SystemStage`].
+/// A [`SystemParam`] that can be used to schedule operations on the world that will be run, in
+/// order, at the end of the current [`SystemStage`].
+///
+/// Entities can be spawned and modified with typed commands:
 ///
-/// This is a shortcut for [`ResMut<CommandQueue>`].
-#[derive(Deref, DerefMut)]
-pub struct Commands<'a>(RefMut<'a, CommandQueue>);
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # #[derive(HasSchema, Clone, Default)]
+/// # #[repr(C)]
+/// # struct Pos(f32, f32);
+/// # #[derive(HasSchema, Clone, Default)]
+/// # #[repr(C)]
+/// # struct Vel(f32, f32);
+/// fn spawn_system(mut commands: Commands) {
+///     let entity = commands.spawn().insert(Pos(0.0, 0.0)).insert(Vel(1.0, 1.0)).id();
+///     commands.entity(entity).remove::<Vel>();
+/// }
+/// ```
+///
+/// Any system can also be scheduled with [`CommandQueue::add()`], which [`Commands`] dereferences
+/// to.
+pub struct Commands<'a> {
+    queue: RefMut<'a, CommandQueue>,
+    world: &'a World,
+}
+
+impl<'a> Commands<'a> {
+    /// Borrow the [`CommandQueue`] of the `world`, initializing it if needed.
+    ///
+    /// This is useful for queueing commands from outside of a system, such as from scripts.
+    pub fn new(world: &'a World) -> Self {
+        world.resources.get_cell::<CommandQueue>().init(world);
+        Commands {
+            queue: world.resources.get_mut::<CommandQueue>().unwrap(),
+            world,
+        }
+    }
+
+    /// Create a new entity, returning [`EntityCommands`] that can be used to add components to it.
+    ///
+    /// The entity is reserved immediately, so its ID can be used right away, but it only becomes
+    /// alive, and gets its components, at the end of the stage.
+    ///
+    /// > **Note:** This borrows the [`Entities`] resource for a moment, so [`Commands`] can't be
+    /// > used in a system that borrows [`Entities`] mutably, see [`IntoSystem::try_system()`].
+    pub fn spawn(&mut self) -> EntityCommands<'_, 'a> {
+        let entity = self.world.resource::<Entities>().reserve();
+        self.entity(entity)
+    }
+
+    /// Get the [`EntityCommands`] for an existing entity.
+    pub fn entity(&mut self, entity: Entity) -> EntityCommands<'_, 'a> {
+        EntityCommands {
+            commands: self,
+            entity,
+        }
+    }
+
+    /// Kill an entity at the end of the stage.
+    pub fn kill(&mut self, entity: Entity) {
+        self.add(move |mut entities: ResMut<Entities>| entities.kill(entity));
+    }
+}
+
+impl<'a> std::ops::Deref for Commands<'a> {
+    type Target = CommandQueue;
+    fn deref(&self) -> &Self::Target {
+        &self.queue
+    }
+}
+
+impl<'a> std::ops::DerefMut for Commands<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.queue
+    }
+}
 
 impl<'a> SystemParam for Commands<'a> {
     type State = AtomicResource<CommandQueue>;
@@ -308,7 +854,328 @@ impl<'a> SystemParam for Commands<'a> {
         cell
     }
 
-    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        Commands(state.borrow_mut().unwrap())
+    fn update_state(world: &World, state: &mut Self::State) {
+        state.init(world);
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.write_resource(CommandQueue::schema());
+        // Spawning reserves entities, which only needs to read the entities, but it must not run
+        // in parallel with other systems that allocate entities, otherwise the IDs wouldn't be
+        // deterministic.
+        access.read_resource(Entities::schema());
+        access.write_resource(EntityReservations::schema());
+    }
+
+    fn borrow<'s>(world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        Commands {
+            queue: state.borrow_mut().unwrap(),
+            world,
+        }
+    }
+}
+
+/// Commands for a single entity, created with [`Commands::spawn()`] or [`Commands::entity()`].
+///
+/// The operations are applied at the end of the stage, in the order that they were queued. Operations
+/// on entities that have been killed by then are ignored.
+pub struct EntityCommands<'c, 'a> {
+    commands: &'c mut Commands<'a>,
+    entity: Entity,
+}
+
+impl<'c, 'a> EntityCommands<'c, 'a> {
+    /// Get the entity that the commands are for.
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+
+    /// Insert a component, replacing the existing one if present.
+    pub fn insert<T: HasSchema>(&mut self, component: T) -> &mut Self {
+        self.insert_box(SchemaBox::new(component))
+    }
+
+    /// Insert a type-erased component, replacing the existing one if present.
+    pub fn insert_box(&mut self, component: SchemaBox) -> &mut Self {
+        let entity = self.entity;
+        let mut component = Some(component);
+        self.commands.add(move |world: &World| {
+            let component = component.take().unwrap();
+            if world.resource::<Entities>().is_alive(entity) {
+                let store = world.components.get_by_schema(component.schema());
+                store.borrow_mut().insert_box(entity, component);
+            }
+        });
+        self
+    }
+
+    /// Remove a component.
+    pub fn remove<T: HasSchema>(&mut self) -> &mut Self {
+        self.remove_by_schema(T::schema())
+    }
+
+    /// Remove the component with the given schema.
+    pub fn remove_by_schema(&mut self, schema: &'static Schema) -> &mut Self {
+        let entity = self.entity;
+        self.commands.add(move |world: &World| {
+            world
+                .components
+                .get_by_schema(schema)
+                .borrow_mut()
+                .remove_box(entity);
+        });
+        self
+    }
+
+    /// Kill the entity.
+    pub fn kill(&mut self) {
+        self.commands.kill(self.entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Log(Vec<&'static str>);
+
+    #[test]
+    fn system_ordering() {
+        let world = World::new();
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system((|mut log: ResMutInit<Log>| log.0.push("render")).after("physics"));
+        stage.add_system((|mut log: ResMutInit<Log>| log.0.push("input")).system());
+        stage.add_system(
+            (|mut log: ResMutInit<Log>| log.0.push("physics"))
+                .label("physics")
+                .after("input_label"),
+        );
+        stage.add_system((|mut log: ResMutInit<Log>| log.0.push("audio")).system());
+        stage.add_system(
+            (|mut log: ResMutInit<Log>| log.0.push("input_label")).label("input_label"),
+        );
+
+        stage.run(&world);
+        assert_eq!(
+            world.resource::<Log>().0,
+            ["input", "audio", "input_label", "physics", "render"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cycle detected in the system ordering for stage `Update`")]
+    fn system_ordering_cycle() {
+        let mut stage = SimpleSystemStage::new(CoreStage::Update);
+        stage.add_system((|| ()).label("a").after("b"));
+        stage.add_system((|| ()).label("b").after("a"));
+    }
+
+    #[test]
+    fn exclusive_systems() {
+        #[derive(HasSchema, Clone, Default)]
+        struct Level(u32);
+
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_system_to_stage(Update, |mut log: ResMutInit<Log>| log.0.push("update"))
+            .add_system_to_stage(PostUpdate, |mut log: ResMutInit<Log>| {
+                log.0.push("post_update")
+            })
+            .add_exclusive_system_to_stage(
+                Update,
+                (|world: &mut World| {
+                    world.resource_mut::<Log>().0.push("spawn");
+                    world.resources.remove::<Level>();
+                })
+                .exclusive_system()
+                .after("load")
+                .run_if(resource_exists::<Level>()),
+            )
+            .add_exclusive_system_to_stage(
+                Update,
+                (|world: &mut World| {
+                    world.resource_mut::<Log>().0.push("load");
+                    world.init_resource::<Level>();
+                })
+                .exclusive_system()
+                .label("load")
+                .run_if(|level: Option<Res<Level>>| level.is_none()),
+            );
+
+        stages.run(&mut world);
+        stages.run(&mut world);
+        assert_eq!(
+            world.resource::<Log>().0,
+            [
+                "update",
+                "load",
+                "spawn",
+                "post_update",
+                "update",
+                "load",
+                "spawn",
+                "post_update"
+            ]
+        );
+    }
+
+    #[test]
+    fn exclusive_systems_follow_stage_run_conditions() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_stage_run_condition(Update, resource_exists::<Log>())
+            .add_exclusive_system_to_stage(Update, |world: &mut World| {
+                world.resource_mut::<Log>().0.push("exclusive");
+            });
+
+        stages.run(&mut world);
+        assert!(world.get_resource::<Log>().is_none());
+        world.init_resource::<Log>();
+        stages.run(&mut world);
+        assert_eq!(world.resource::<Log>().0, ["exclusive"]);
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Pos(u32);
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Vel(u32);
+
+    fn move_system(mut pos: CompMut<Pos>, vel: Comp<Vel>, entities: Res<Entities>) {
+        for (_, (pos, vel)) in entities.iter_with((&mut pos, &vel)) {
+            pos.0 = pos.0 * 2 + vel.0;
+        }
+    }
+
+    fn accelerate(mut vel: CompMut<Vel>, entities: Res<Entities>) {
+        for (_, vel) in entities.iter_with(&mut vel) {
+            vel.0 += 1;
+        }
+    }
+
+    fn log_positions(pos: Comp<Pos>, entities: Res<Entities>, mut log: ResMutInit<Log>) {
+        for (_, pos) in entities.iter_with(&pos) {
+            log.0.push(if pos.0 % 2 == 0 { "even" } else { "odd" });
+        }
+    }
+
+    fn log_velocities(vel: Comp<Vel>, entities: Res<Entities>) {
+        for (_, vel) in entities.iter_with(&vel) {
+            assert!(vel.0 > 0);
+        }
+    }
+
+    #[test]
+    fn parallel_stage_matches_sequential() {
+        let run = |mut stages: SystemStages| {
+            let mut world = World::new();
+            world.run_system(
+                |mut entities: ResMut<Entities>, mut pos: CompMut<Pos>, mut vel: CompMut<Vel>| {
+                    for i in 0..10 {
+                        let entity = entities.create();
+                        pos.insert(entity, Pos(i));
+                        vel.insert(entity, Vel(i % 3 + 1));
+                    }
+                },
+                (),
+            );
+            stages
+                .add_system_to_stage(Update, move_system)
+                .add_system_to_stage(Update, log_velocities)
+                .add_system_to_stage(Update, accelerate)
+                .add_system_to_stage(Update, log_positions);
+            for _ in 0..3 {
+                stages.run(&mut world);
+            }
+            let log = world.resource::<Log>().0.clone();
+            log
+        };
+
+        let mut parallel_stages = SystemStages::with_core_stages();
+        parallel_stages.replace_stage(Update, ParallelSystemStage::new(Update));
+
+        assert_eq!(run(SystemStages::with_core_stages()), run(parallel_stages));
+    }
+
+    #[test]
+    fn typed_commands() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_system_to_stage(Update, |mut commands: Commands| {
+                let a = commands.spawn().insert(Pos(1)).insert(Vel(2)).id();
+                let b = commands.spawn().insert(Pos(3)).id();
+                commands.entity(a).remove::<Pos>();
+                commands.kill(b);
+                // Components inserted after the entity is killed are ignored.
+                commands.entity(b).insert(Vel(4));
+            })
+            .add_system_to_stage(Update, |pos: Comp<Pos>, vel: Comp<Vel>| {
+                // The commands haven't been applied yet.
+                assert!(pos.bitset().bit_none() && vel.bitset().bit_none());
+            });
+        stages.run(&mut world);
+
+        let entities = world.resource::<Entities>();
+        let pos = world.components.get::<Pos>().borrow();
+        let vel = world.components.get::<Vel>().borrow();
+        let alive = entities
+            .iter_with_bitset(entities.bitset())
+            .collect::<Vec<_>>();
+        assert_eq!(alive.len(), 1);
+        assert!(pos.get(alive[0]).is_none());
+        assert_eq!(vel.get(alive[0]).map(|x| x.0), Some(2));
+        assert_eq!(vel.iter().count(), 1);
+    }
+
+    #[test]
+    fn parallel_stage_batches() {
+        let mut stage = ParallelSystemStage::new(Update);
+        stage.add_system(move_system.system());
+        stage.add_system(log_velocities.system());
+        stage.add_system(accelerate.system());
+        stage.add_system(log_positions.system());
+        stage.add_system((|_world: &World| ()).system());
+
+        assert_eq!(stage.batches(), [vec![0, 1], vec![2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn parallel_stage_spawning() {
+        let mut stage = ParallelSystemStage::new(Update);
+        stage.add_system(
+            (|mut commands: Commands| {
+                commands.spawn().insert(Pos(1));
+            })
+            .system(),
+        );
+        stage.add_system(
+            (|mut commands: Commands| {
+                commands.spawn().insert(Vel(2));
+            })
+            .system(),
+        );
+        stage.add_system((|_entities: Res<Entities>| ()).system());
+        stage.add_system((|_entities: ResMut<Entities>| ()).system());
+
+        // Both spawning systems reserve entities, so they run one after the other and the IDs are
+        // the same as when running them in order. Reading the entities doesn't conflict with
+        // reserving them, but writing to them does.
+        assert_eq!(stage.batches(), [vec![0, 2], vec![1], vec![3]]);
+        let world = World::new();
+        stage.run(&world);
+
+        let entities = world.resource::<Entities>();
+        let alive = entities
+            .iter_with_bitset(entities.bitset())
+            .collect::<Vec<_>>();
+        assert_eq!(alive.len(), 2);
+        let pos = world.components.get::<Pos>().borrow();
+        let vel = world.components.get::<Vel>().borrow();
+        assert_eq!(pos.get(alive[0]).map(|x| x.0), Some(1));
+        assert_eq!(vel.get(alive[1]).map(|x| x.0), Some(2));
     }
 }
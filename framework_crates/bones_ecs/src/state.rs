@@ -0,0 +1,352 @@
+//! State machines, with systems that run when entering or exiting a state.
+//!
+//! A state is any type implementing [`HasSchema`], [`Clone`] and [`Eq`], usually an enum. It is
+//! added to the [`SystemStages`] with [`SystemStages::add_state()`], and systems are registered
+//! for it with [`SystemStages::add_state_system()`]:
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! #[derive(HasSchema, Clone, Default, PartialEq, Eq)]
+//! #[repr(u8)]
+//! enum GameState {
+//!     #[default]
+//!     Menu,
+//!     Playing,
+//! }
+//! # fn spawn_level() {}
+//! # fn despawn_level() {}
+//! # fn move_player() {}
+//!
+//! let mut stages = SystemStages::with_core_stages();
+//! stages
+//!     .add_state(GameState::Menu)
+//!     .add_state_system(OnEnter(GameState::Playing), spawn_level)
+//!     .add_state_system(OnExit(GameState::Playing), despawn_level)
+//!     .add_state_system(OnUpdate(GameState::Playing), move_player);
+//! ```
+//!
+//! The current state is stored in the [`State`] resource, and systems change it by setting the
+//! [`NextState`] resource. Transitions are applied at the start of [`SystemStages::run()`], after
+//! the startup systems: the [`OnExit`] systems of the old state are run, then the [`State`] is
+//! updated and the [`OnEnter`] systems of the new state are run. On the first run, the state is
+//! initialized and its [`OnEnter`] systems are run.
+//!
+//! Because the state is stored in the world, it is saved and restored along with world snapshots.
+//! [`State`] and [`NextState`] have schemas made of the schema of the state type, so they are also
+//! serialized and compared along with the world, as long as the state type can be.
+
+use std::{
+    alloc::Layout,
+    any::{type_name, Any, TypeId},
+    sync::OnceLock,
+};
+
+use bones_schema::raw_fns::{RawClone, RawDrop};
+use bones_utils::parking_lot::RwLock;
+
+use crate::prelude::*;
+
+/// Resource containing the current state of type `S`.
+///
+/// It is inserted by [`SystemStages::run()`] for every state added with
+/// [`SystemStages::add_state()`]. Use [`NextState`] to change it.
+#[derive(Clone, Debug)]
+#[repr(C)]
+pub struct State<S>(S);
+
+// SAFE: We return a valid schema. `State<S>` has a single field, so it has the same layout as `S`,
+// and the functions of the schema of `S` work for it too.
+unsafe impl<S: HasSchema + Clone> HasSchema for State<S> {
+    fn schema() -> &'static Schema {
+        static SCHEMAS: OnceLock<RwLock<HashMap<TypeId, &'static Schema>>> = OnceLock::new();
+        assert_eq!(Layout::new::<Self>(), Layout::new::<S>());
+
+        let map = SCHEMAS.get_or_init(|| RwLock::new(HashMap::default()));
+        let existing_schema = { map.read().get(&TypeId::of::<Self>()).copied() };
+        if let Some(existing_schema) = existing_schema {
+            return existing_schema;
+        }
+
+        // States don't have to implement `Default`, but the schema has a default function if the
+        // state type has one, so that it can be deserialized.
+        let state_schema = S::schema();
+        let schema = SCHEMA_REGISTRY.register(SchemaData {
+            name: "State".into(),
+            full_name: type_name::<Self>().into(),
+            type_id: Some(TypeId::of::<Self>()),
+            kind: SchemaKind::Struct(StructSchemaInfo {
+                fields: vec![StructFieldInfo {
+                    name: None,
+                    schema: state_schema,
+                }],
+            }),
+            clone_fn: Some(<Self as RawClone>::raw_clone_cb()),
+            drop_fn: Some(<Self as RawDrop>::raw_drop_cb()),
+            default_fn: state_schema.default_fn.clone(),
+            eq_fn: state_schema.eq_fn.clone(),
+            hash_fn: state_schema.hash_fn.clone(),
+            type_data: default(),
+        });
+        map.write().insert(TypeId::of::<Self>(), schema);
+        schema
+    }
+}
+
+impl<S> State<S> {
+    /// Get the current state.
+    pub fn get(&self) -> &S {
+        &self.0
+    }
+}
+
+/// Resource used to change the [`State`] of type `S`.
+///
+/// The transition is applied the next time that the [`SystemStages`] are run.
+#[derive(HasSchema, Clone, Debug)]
+#[repr(C)]
+pub struct NextState<S>(
+    /// The pending state, if any, in a vec so that it has a schema.
+    SVec<S>,
+)
+where
+    S: HasSchema;
+
+impl<S: HasSchema> Default for NextState<S> {
+    fn default() -> Self {
+        Self(SVec::new())
+    }
+}
+
+impl<S: HasSchema> NextState<S> {
+    /// Transition to the given state the next time that the stages are run.
+    pub fn set(&mut self, state: S) {
+        self.0.clear();
+        self.0.push(state);
+    }
+
+    /// Get the state that will be transitioned to, if any.
+    pub fn get(&self) -> Option<&S> {
+        self.0.first()
+    }
+}
+
+/// When a system added with [`SystemStages::add_state_system()`] is run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateSchedule<S> {
+    /// Run the system once, when entering the state.
+    OnEnter(S),
+    /// Run the system once, when exiting the state.
+    OnExit(S),
+    /// Run the system in the [`CoreStage::Update`] stage, while in the state.
+    OnUpdate(S),
+}
+
+/// Run condition that returns `true` if the [`State`] of type `S` is `state`.
+pub fn in_state<S: HasSchema + Clone + Eq>(state: S) -> StaticSystem<(), bool> {
+    (move |current: Option<Res<State<S>>>| current.is_some_and(|current| current.0 == state))
+        .system()
+}
+
+/// The systems of a state added to a [`SystemStages`], with the type of the state erased.
+pub(crate) trait StateMachine: Send + Sync {
+    /// Apply the pending transition, if any, running the systems for it.
+    fn apply_transition(&mut self, world: &World);
+    /// Get the state machine as [`Any`], so that it can be downcast to its concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// List the systems that run on the transitions, for [`SystemStages::schedule_info()`].
+    fn info(&self) -> StateInfo;
+}
+
+/// The systems that are run when entering or exiting the states of type `S`.
+struct StateSystems<S> {
+    initial: S,
+    on_enter: Vec<(S, StaticSystem<(), ()>)>,
+    on_exit: Vec<(S, StaticSystem<(), ()>)>,
+}
+
+impl<S: HasSchema + Clone + Eq> StateMachine for StateSystems<S> {
+    fn apply_transition(&mut self, world: &World) {
+        let current = world.resources.get::<State<S>>().map(|x| x.0.clone());
+        let next = world
+            .resources
+            .get_mut::<NextState<S>>()
+            .and_then(|mut next| next.0.pop());
+        let (exit, enter) = match (current, next) {
+            (None, next) => (None, next.unwrap_or_else(|| self.initial.clone())),
+            (Some(current), Some(next)) if current != next => (Some(current), next),
+            _ => return,
+        };
+
+        if let Some(exit) = exit {
+            for (_, system) in self.on_exit.iter_mut().filter(|(s, _)| *s == exit) {
+                system.run(world, ());
+            }
+            crate::stage::drain_command_queue(world);
+        }
+
+        world.resources.insert(State(enter.clone()));
+        for (_, system) in self.on_enter.iter_mut().filter(|(s, _)| *s == enter) {
+            system.run(world, ());
+        }
+        crate::stage::drain_command_queue(world);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn info(&self) -> StateInfo {
+        let systems = |systems: &[(S, StaticSystem<(), ()>)]| {
+            systems
+                .iter()
+                .map(|(state, system)| (SchemaRef::new(state).to_string(), SystemInfo::new(system)))
+                .collect()
+        };
+        StateInfo {
+            name: S::schema().name.to_string(),
+            on_exit: systems(&self.on_exit),
+            on_enter: systems(&self.on_enter),
+        }
+    }
+}
+
+impl SystemStages {
+    /// Add a state of type `S`, starting in the `initial` state.
+    ///
+    /// If the state has already been added, only its initial state is changed.
+    pub fn add_state<S: HasSchema + Clone + Eq>(&mut self, initial: S) -> &mut Self {
+        if let Some(systems) = self.state_systems::<S>() {
+            systems.initial = initial;
+        } else {
+            self.states.push(Box::new(StateSystems {
+                initial,
+                on_enter: Vec::new(),
+                on_exit: Vec::new(),
+            }));
+        }
+        self
+    }
+
+    /// Add a system that runs when entering or exiting a state, or while in a state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state hasn't been added with [`add_state()`][Self::add_state].
+    #[track_caller]
+    pub fn add_state_system<S, Args, Sys>(
+        &mut self,
+        schedule: StateSchedule<S>,
+        system: Sys,
+    ) -> &mut Self
+    where
+        S: HasSchema + Clone + Eq,
+        Sys: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+    {
+        let Some(systems) = self.state_systems::<S>() else {
+            panic!(
+                "State `{}` has not been added with `add_state()`",
+                std::any::type_name::<S>()
+            );
+        };
+        match schedule {
+            StateSchedule::OnEnter(state) => systems.on_enter.push((state, system.system())),
+            StateSchedule::OnExit(state) => systems.on_exit.push((state, system.system())),
+            StateSchedule::OnUpdate(state) => {
+                self.add_system_to_stage(CoreStage::Update, system.run_if(in_state(state)));
+            }
+        }
+        self
+    }
+
+    /// Apply the pending transitions of every state.
+    pub(crate) fn apply_state_transitions(&mut self, world: &World) {
+        for state in &mut self.states {
+            state.apply_transition(world);
+        }
+    }
+
+    fn state_systems<S: HasSchema + Clone + Eq>(&mut self) -> Option<&mut StateSystems<S>> {
+        self.states
+            .iter_mut()
+            .find_map(|state| state.as_any_mut().downcast_mut::<StateSystems<S>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(HasSchema, Clone, Default, Debug, PartialEq, Eq)]
+    #[repr(u8)]
+    enum Screen {
+        #[default]
+        Menu,
+        Playing,
+    }
+
+    #[derive(HasSchema, Clone, Default)]
+    struct Log(Vec<&'static str>);
+
+    #[test]
+    fn state_transitions() {
+        let log = |message| move |mut log: ResMutInit<Log>| log.0.push(message);
+
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_state(Screen::Menu)
+            .add_state_system(OnEnter(Screen::Menu), log("enter menu"))
+            .add_state_system(OnExit(Screen::Menu), log("exit menu"))
+            .add_state_system(OnEnter(Screen::Playing), log("enter playing"))
+            .add_state_system(OnUpdate(Screen::Playing), log("playing"))
+            .add_system_to_stage(Update, |mut next: ResMutInit<NextState<Screen>>| {
+                next.set(Screen::Playing)
+            });
+
+        stages.run(&mut world);
+        assert_eq!(world.resource::<State<Screen>>().get(), &Screen::Menu);
+        let snapshot = world.clone();
+
+        stages.run(&mut world);
+        stages.run(&mut world);
+        assert_eq!(
+            world.resource::<Log>().0,
+            [
+                "enter menu",
+                "exit menu",
+                "enter playing",
+                "playing",
+                "playing"
+            ]
+        );
+
+        // The state is restored along with a snapshot.
+        assert_eq!(snapshot.resource::<State<Screen>>().get(), &Screen::Menu);
+        assert_eq!(
+            snapshot.resource::<NextState<Screen>>().get(),
+            Some(&Screen::Playing)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn state_save_load() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages.add_state(Screen::Menu);
+        stages.run(&mut world);
+        world.init_resource::<NextState<Screen>>();
+        world
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Playing);
+
+        assert!(world.unserializable_types().is_empty());
+        let mut loaded = World::from_yaml(&world.to_yaml().unwrap()).unwrap();
+        assert!(world.diff(&loaded).is_empty(), "{}", world.diff(&loaded));
+        assert_eq!(loaded.resource::<State<Screen>>().get(), &Screen::Menu);
+
+        // The pending transition is applied to the loaded world.
+        stages.run(&mut loaded);
+        assert_eq!(loaded.resource::<State<Screen>>().get(), &Screen::Playing);
+    }
+}
@@ -1,9 +1,178 @@
 //! Implements the system API for the ECS.
 
-use std::sync::Arc;
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use crate::prelude::*;
 
+/// A unique identifier for a system.
+///
+/// A new ID is allocated every time a function is converted into a system with
+/// [`IntoSystem::system()`], and it is passed to [`SystemParam::get_system_state()`] so that
+/// parameters may store per-system data in the [`World`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SystemId(u64);
+
+impl SystemId {
+    /// Allocate a new, unique [`SystemId`].
+    pub fn new_unique() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// The change detection ticks of a system run.
+///
+/// Ticks come from a counter owned by the [`World`], and every run of a system gets a new one.
+/// Components that are inserted or mutably borrowed while the system runs are marked with the tick
+/// of the run, and the world remembers it as the last run of the system. The [`Added`] and
+/// [`Changed`] query items, and the [`ComponentStore`] methods like
+/// [`is_changed()`][ComponentStore::is_changed], only match components marked with a tick newer
+/// than the last run of the system, so that a system sees every change once, no matter when it
+/// happened.
+///
+/// The counter and the last runs are cloned along with the world, so a system run on a restored
+/// snapshot sees the same changes as it would have on the world that the snapshot was taken from.
+/// The systems of a batch of a [`ParallelSystemStage`] share a tick, so that the ticks don't depend
+/// on the order that the threads run in.
+///
+/// Outside of systems, and in [`ExclusiveSystem`]s, components are marked with the current tick of
+/// the counter, and every component counts as added and changed. Systems run with
+/// [`World::run_system()`] and [`Commands`] run only once, so their last run isn't recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SystemTicks {
+    /// The tick of the previous run of the system, or `0` if it didn't run yet.
+    pub last_run: u64,
+    /// The tick of the current run of the system.
+    pub this_run: u64,
+}
+
+thread_local! {
+    /// The ticks of the system running on the current thread.
+    static CURRENT_TICKS: Cell<Option<SystemTicks>> = const { Cell::new(None) };
+    /// The tick shared by the systems of the parallel batch running on the current thread.
+    static BATCH_TICK: Cell<Option<u64>> = const { Cell::new(None) };
+    /// Whether or not the systems running on the current thread are only run once.
+    static ONE_SHOT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The change detection counter of a [`World`], and the last run of every system on it, see
+/// [`SystemTicks`].
+///
+/// It is shared by the [`ComponentStores`] of the world, and copied when they are cloned.
+#[derive(Debug)]
+pub(crate) struct ChangeTicks {
+    /// The next tick to allocate.
+    tick: AtomicU64,
+    /// The tick of the last run of every system.
+    last_runs: parking_lot::Mutex<HashMap<SystemId, u64>>,
+}
+
+impl Default for ChangeTicks {
+    fn default() -> Self {
+        Self {
+            tick: AtomicU64::new(1),
+            last_runs: Default::default(),
+        }
+    }
+}
+
+impl Clone for ChangeTicks {
+    fn clone(&self) -> Self {
+        Self {
+            tick: AtomicU64::new(self.tick.load(Ordering::Relaxed)),
+            last_runs: parking_lot::Mutex::new(self.last_runs.lock().clone()),
+        }
+    }
+}
+
+impl ChangeTicks {
+    /// Allocate a new tick.
+    pub(crate) fn next(&self) -> u64 {
+        self.tick.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Get the ticks for a new run of a system, recording it as the last run of the system.
+    pub(crate) fn start_system(&self, system: SystemId) -> SystemTicks {
+        let this_run = BATCH_TICK.with(Cell::get).unwrap_or_else(|| self.next());
+        let mut last_runs = self.last_runs.lock();
+        let last_run = if ONE_SHOT.with(Cell::get) {
+            last_runs.get(&system).copied()
+        } else {
+            last_runs.insert(system, this_run)
+        };
+        SystemTicks {
+            last_run: last_run.unwrap_or(0),
+            this_run,
+        }
+    }
+
+    /// Run a function in which every system started gets the given tick, for the systems of a
+    /// parallel batch.
+    pub(crate) fn batch<R>(tick: u64, f: impl FnOnce() -> R) -> R {
+        /// Restores the previous tick when dropped, even if the system panics.
+        struct Restore(Option<u64>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                BATCH_TICK.with(|tick| tick.set(self.0));
+            }
+        }
+        let _restore = Restore(BATCH_TICK.with(|x| x.replace(Some(tick))));
+        f()
+    }
+
+    /// Run a function in which the systems started are only run once, so that their last runs
+    /// aren't recorded and don't accumulate in the world.
+    pub(crate) fn one_shot<R>(f: impl FnOnce() -> R) -> R {
+        /// Restores the previous flag when dropped, even if the system panics.
+        struct Restore(bool);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                ONE_SHOT.with(|one_shot| one_shot.set(self.0));
+            }
+        }
+        let _restore = Restore(ONE_SHOT.with(|x| x.replace(true)));
+        f()
+    }
+}
+
+impl SystemTicks {
+    /// Get the ticks of the system running on the current thread.
+    ///
+    /// Outside of systems, the last run is `0` and the current run is the current tick of the
+    /// given counter.
+    pub(crate) fn current(world_ticks: &ChangeTicks) -> Self {
+        CURRENT_TICKS.with(Cell::get).unwrap_or_else(|| Self {
+            last_run: 0,
+            this_run: world_ticks.tick.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Whether or not a component marked with `tick` changed since the last run.
+    #[inline]
+    pub fn is_newer(&self, tick: u64) -> bool {
+        tick > self.last_run
+    }
+
+    /// Run a function with these ticks as the ticks of the system running on the current thread.
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        /// Restores the previous ticks when dropped, even if the system panics.
+        struct Restore(Option<SystemTicks>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT_TICKS.with(|ticks| ticks.set(self.0));
+            }
+        }
+        let _restore = Restore(CURRENT_TICKS.with(|ticks| ticks.replace(Some(self))));
+        f()
+    }
+}
+
 /// Trait implemented by systems.
 pub trait System<In, Out> {
     /// Run the system.
@@ -18,6 +187,13 @@ pub struct StaticSystem<In, Out> {
     pub run: Box<dyn FnMut(&World, In) -> Out + Send + Sync>,
     /// A best-effort name for the system, for diagnostic purposes.
     pub name: &'static str,
+    /// The labels and ordering constraints used to order the system inside of a [`SystemStage`].
+    pub ordering: SystemOrdering,
+    /// The data in the world that the system accesses.
+    pub access: SystemAccess,
+    /// The names of the [run conditions][Condition] of the system, for introspection with
+    /// [`SystemStages::schedule_info()`].
+    pub run_conditions: Vec<&'static str>,
 }
 
 impl<In, Out> System<In, Out> for StaticSystem<In, Out> {
@@ -29,6 +205,403 @@ impl<In, Out> System<In, Out> for StaticSystem<In, Out> {
     }
 }
 
+/// A system with exclusive, mutable access to the [`World`].
+///
+/// Exclusive systems can do things that normal systems can't, like inserting resources with
+/// [`World::insert_resource()`]. They are added to [`SystemStages`] with
+/// [`SystemStages::add_exclusive_system_to_stage()`], and run at the end of the stage, after the
+/// other systems in the stage and their [`Commands`].
+///
+/// They are ordered relative to the other exclusive systems of the stage with
+/// [`label()`][Self::label], [`before()`][Self::before] and [`after()`][Self::after], the same way
+/// as normal systems.
+pub struct ExclusiveSystem {
+    /// This is run every time the system is executed
+    pub run: Box<dyn FnMut(&mut World) + Send + Sync>,
+    /// A best-effort name for the system, for diagnostic purposes.
+    pub name: &'static str,
+    /// The labels and ordering constraints used to order the system relative to the other
+    /// exclusive systems of its stage.
+    pub ordering: SystemOrdering,
+    /// The names of the [run conditions][Condition] of the system, for introspection with
+    /// [`SystemStages::schedule_info()`].
+    pub run_conditions: Vec<&'static str>,
+}
+
+impl ExclusiveSystem {
+    /// Run the system.
+    pub fn run(&mut self, world: &mut World) {
+        (self.run)(world)
+    }
+
+    /// Attach a label to the system, so that other exclusive systems can be ordered relative to it.
+    pub fn label(mut self, label: impl SystemLabel) -> Self {
+        self.ordering.labels.push(label.name());
+        self
+    }
+
+    /// Make the system run before the exclusive systems with the given label.
+    pub fn before(mut self, label: impl SystemLabel) -> Self {
+        self.ordering.before.push(label.name());
+        self
+    }
+
+    /// Make the system run after the exclusive systems with the given label.
+    pub fn after(mut self, label: impl SystemLabel) -> Self {
+        self.ordering.after.push(label.name());
+        self
+    }
+
+    /// Only run the system if the given [run condition][Condition] returns `true`.
+    ///
+    /// If this is called more than once, all of the conditions must return `true`.
+    pub fn run_if<CondArgs, C>(mut self, condition: C) -> Self
+    where
+        C: IntoSystem<CondArgs, (), bool, Sys = StaticSystem<(), bool>>,
+    {
+        let mut condition = condition.system();
+        self.run_conditions.push(condition.name);
+        let mut run = self.run;
+        self.run = Box::new(move |world| {
+            if condition.run(world, ()) {
+                run(world);
+            }
+        });
+        self
+    }
+}
+
+/// Converts a function taking `&mut World` into an [`ExclusiveSystem`].
+pub trait IntoExclusiveSystem {
+    /// Convert into an [`ExclusiveSystem`].
+    fn exclusive_system(self) -> ExclusiveSystem;
+}
+
+impl IntoExclusiveSystem for ExclusiveSystem {
+    fn exclusive_system(self) -> ExclusiveSystem {
+        self
+    }
+}
+
+impl<F> IntoExclusiveSystem for F
+where
+    F: FnMut(&mut World) + Send + Sync + 'static,
+{
+    fn exclusive_system(self) -> ExclusiveSystem {
+        ExclusiveSystem {
+            run: Box::new(self),
+            name: std::any::type_name::<F>(),
+            ordering: default(),
+            run_conditions: default(),
+        }
+    }
+}
+
+/// Trait for things that may be used to label a system, so that other systems in the same
+/// [`SystemStage`] can be ordered relative to it.
+///
+/// Two labels are considered the same if they have the same name.
+pub trait SystemLabel {
+    /// Returns the name of the label.
+    fn name(&self) -> Ustr;
+}
+
+impl SystemLabel for &str {
+    fn name(&self) -> Ustr {
+        ustr(self)
+    }
+}
+
+impl SystemLabel for String {
+    fn name(&self) -> Ustr {
+        ustr(self)
+    }
+}
+
+impl SystemLabel for Ustr {
+    fn name(&self) -> Ustr {
+        *self
+    }
+}
+
+/// The labels and ordering constraints of a system.
+///
+/// Constraints that refer to labels that no system in the stage has are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct SystemOrdering {
+    /// The labels attached to the system.
+    pub labels: Vec<Ustr>,
+    /// The system must run before all systems with these labels.
+    pub before: Vec<Ustr>,
+    /// The system must run after all systems with these labels.
+    pub after: Vec<Ustr>,
+}
+
+impl SystemOrdering {
+    /// Whether or not the system must run before the system with the `other` ordering.
+    pub fn runs_before(&self, other: &SystemOrdering) -> bool {
+        self.before.iter().any(|label| other.labels.contains(label))
+            || other.after.iter().any(|label| self.labels.contains(label))
+    }
+}
+
+/// The data in the [`World`] that a system, or a [`SystemParam`], accesses.
+///
+/// This is used by the [`ParallelSystemStage`] to decide which systems may run at the same time.
+#[derive(Clone, Debug, Default)]
+pub struct SystemAccess {
+    /// Whether or not anything in the world may be accessed, such as with a [`&World`][World]
+    /// parameter.
+    pub world: bool,
+    /// The resources that are read.
+    pub resource_reads: Vec<&'static Schema>,
+    /// The resources that are written to.
+    pub resource_writes: Vec<&'static Schema>,
+    /// The component stores that are read.
+    pub component_reads: Vec<&'static Schema>,
+    /// The component stores that are written to.
+    pub component_writes: Vec<&'static Schema>,
+    /// The reads and writes that were added while a write to the same resource or component store
+    /// had already been added, or writes that were added while a read had.
+    ///
+    /// This is only meaningful for the borrows collected with [`SystemParam::borrows()`], where
+    /// it means that the parameters of the system can't be borrowed at the same time.
+    pub conflicts: Vec<BorrowConflict>,
+}
+
+impl SystemAccess {
+    /// Add read access to the resource with the given schema.
+    pub fn read_resource(&mut self, schema: &'static Schema) {
+        if contains_schema(&self.resource_writes, schema) {
+            self.add_conflict(BorrowConflict::Resource(schema));
+        }
+        push_schema(&mut self.resource_reads, schema);
+    }
+
+    /// Add write access to the resource with the given schema.
+    pub fn write_resource(&mut self, schema: &'static Schema) {
+        if contains_schema(&self.resource_reads, schema)
+            || contains_schema(&self.resource_writes, schema)
+        {
+            self.add_conflict(BorrowConflict::Resource(schema));
+        }
+        push_schema(&mut self.resource_writes, schema);
+    }
+
+    /// Add read access to the component store with the given schema.
+    pub fn read_component(&mut self, schema: &'static Schema) {
+        if contains_schema(&self.component_writes, schema) {
+            self.add_conflict(BorrowConflict::Component(schema));
+        }
+        push_schema(&mut self.component_reads, schema);
+    }
+
+    /// Add write access to the component store with the given schema.
+    pub fn write_component(&mut self, schema: &'static Schema) {
+        if contains_schema(&self.component_reads, schema)
+            || contains_schema(&self.component_writes, schema)
+        {
+            self.add_conflict(BorrowConflict::Component(schema));
+        }
+        push_schema(&mut self.component_writes, schema);
+    }
+
+    /// Add all of the access from `other`.
+    ///
+    /// This doesn't add [`conflicts`][Self::conflicts] between the two accesses, because it is
+    /// used to combine the access of things that don't run at the same time, like a system and its
+    /// run conditions.
+    pub fn extend(&mut self, other: &SystemAccess) {
+        self.world |= other.world;
+        for &schema in &other.resource_reads {
+            push_schema(&mut self.resource_reads, schema);
+        }
+        for &schema in &other.resource_writes {
+            push_schema(&mut self.resource_writes, schema);
+        }
+        for &schema in &other.component_reads {
+            push_schema(&mut self.component_reads, schema);
+        }
+        for &schema in &other.component_writes {
+            push_schema(&mut self.component_writes, schema);
+        }
+        for &conflict in &other.conflicts {
+            self.add_conflict(conflict);
+        }
+    }
+
+    fn add_conflict(&mut self, conflict: BorrowConflict) {
+        if !self.conflicts.contains(&conflict) {
+            self.conflicts.push(conflict);
+        }
+    }
+
+    /// Whether or not a system with this access may run at the same time as a system with the
+    /// `other` access.
+    pub fn is_compatible(&self, other: &SystemAccess) -> bool {
+        let overlaps = |a: &[&'static Schema], b: &[&'static Schema]| {
+            a.iter().any(|x| b.iter().any(|y| x.id() == y.id()))
+        };
+        !(self.world
+            || other.world
+            || overlaps(&self.resource_writes, &other.resource_writes)
+            || overlaps(&self.resource_writes, &other.resource_reads)
+            || overlaps(&self.resource_reads, &other.resource_writes)
+            || overlaps(&self.component_writes, &other.component_writes)
+            || overlaps(&self.component_writes, &other.component_reads)
+            || overlaps(&self.component_reads, &other.component_writes))
+    }
+}
+
+fn contains_schema(list: &[&'static Schema], schema: &'static Schema) -> bool {
+    list.iter().any(|x| x.id() == schema.id())
+}
+
+fn push_schema(list: &mut Vec<&'static Schema>, schema: &'static Schema) {
+    if !contains_schema(list, schema) {
+        list.push(schema);
+    }
+}
+
+/// A resource or component store that the parameters of a system borrow mutably more than once,
+/// or both mutably and immutably.
+#[derive(Clone, Copy, Debug)]
+pub enum BorrowConflict {
+    /// A conflicting borrow of a resource.
+    Resource(&'static Schema),
+    /// A conflicting borrow of a component store.
+    Component(&'static Schema),
+}
+
+impl PartialEq for BorrowConflict {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Resource(a), Self::Resource(b)) | (Self::Component(a), Self::Component(b)) => {
+                a.id() == b.id()
+            }
+            _ => false,
+        }
+    }
+}
+impl Eq for BorrowConflict {}
+
+impl std::fmt::Display for BorrowConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Resource(schema) => write!(f, "resource `{}`", schema.full_name),
+            Self::Component(schema) => write!(f, "component `{}`", schema.full_name),
+        }
+    }
+}
+
+/// Error returned by [`IntoSystem::try_system()`] when the parameters of a system can't be
+/// borrowed at the same time, such as a system taking both `Comp<T>` and `CompMut<T>`, or two
+/// `ResMut<T>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictingBorrowsError {
+    /// The name of the system.
+    pub system: &'static str,
+    /// The resources and component stores that are borrowed in conflicting ways.
+    pub conflicts: Vec<BorrowConflict>,
+}
+
+impl std::error::Error for ConflictingBorrowsError {}
+impl std::fmt::Display for ConflictingBorrowsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "System `{}` has parameters with conflicting borrows of ",
+            self.system
+        )?;
+        for (i, conflict) in self.conflicts.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{conflict}")?;
+        }
+        write!(
+            f,
+            ". Something may only be borrowed mutably by a single parameter, and not at the same \
+            time as it is borrowed immutably."
+        )
+    }
+}
+
+/// Extension trait for configuring how a system is scheduled inside of a [`SystemStage`].
+///
+/// # Example
+///
+/// ```
+/// # use bones_ecs::prelude::*;
+/// # #[derive(HasSchema, Clone, Default)]
+/// # struct Paused;
+/// # fn physics() {}
+/// # fn render() {}
+/// let mut stages = SystemStages::with_core_stages();
+/// stages
+///     .add_system_to_stage(CoreStage::Update, render.after("physics"))
+///     .add_system_to_stage(
+///         CoreStage::Update,
+///         physics
+///             .label("physics")
+///             .run_if(resource_exists::<Paused>().not()),
+///     );
+/// ```
+pub trait ConfigureSystem<Args>: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>> {
+    /// Attach a label to the system, so that other systems can be ordered relative to it.
+    fn label(self, label: impl SystemLabel) -> StaticSystem<(), ()>;
+    /// Make the system run before the systems with the given label.
+    fn before(self, label: impl SystemLabel) -> StaticSystem<(), ()>;
+    /// Make the system run after the systems with the given label.
+    fn after(self, label: impl SystemLabel) -> StaticSystem<(), ()>;
+    /// Only run the system if the given [run condition][Condition] returns `true`.
+    ///
+    /// If this is called more than once, all of the conditions must return `true`.
+    fn run_if<CondArgs, C>(self, condition: C) -> StaticSystem<(), ()>
+    where
+        C: IntoSystem<CondArgs, (), bool, Sys = StaticSystem<(), bool>>;
+}
+
+impl<Args, S> ConfigureSystem<Args> for S
+where
+    S: IntoSystem<Args, (), (), Sys = StaticSystem<(), ()>>,
+{
+    fn label(self, label: impl SystemLabel) -> StaticSystem<(), ()> {
+        let mut system = self.system();
+        system.ordering.labels.push(label.name());
+        system
+    }
+
+    fn before(self, label: impl SystemLabel) -> StaticSystem<(), ()> {
+        let mut system = self.system();
+        system.ordering.before.push(label.name());
+        system
+    }
+
+    fn after(self, label: impl SystemLabel) -> StaticSystem<(), ()> {
+        let mut system = self.system();
+        system.ordering.after.push(label.name());
+        system
+    }
+
+    fn run_if<CondArgs, C>(self, condition: C) -> StaticSystem<(), ()>
+    where
+        C: IntoSystem<CondArgs, (), bool, Sys = StaticSystem<(), bool>>,
+    {
+        let mut system = self.system();
+        let mut condition = condition.system();
+        system.access.extend(&condition.access);
+        system.run_conditions.push(condition.name);
+        let mut run = system.run;
+        system.run = Box::new(move |world, ()| {
+            if condition.run(world, ()) {
+                run(world, ());
+            }
+        });
+        system
+    }
+}
+
 /// Converts a function into a [`System`].
 ///
 /// [`IntoSystem`] is automatically implemented for all functions and closures that:
@@ -40,13 +613,28 @@ impl<In, Out> System<In, Out> for StaticSystem<In, Out> {
 /// - [`Res`] and [`ResMut`] parameters to access resources
 /// - [`Comp`] and [`CompMut`] parameters to access components
 /// - [`&World`][World] to access the world directly
+/// - [`Local`] to keep private data in the system between runs
 /// - [`In`] for systems which have an input value. This must be the first argument of the function.
 pub trait IntoSystem<Args, In, Out> {
     /// The type of the system that is output
     type Sys: System<In, Out>;
 
     /// Convert into a [`System`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parameters of the system have conflicting borrows. See
+    /// [`try_system()`][Self::try_system].
     fn system(self) -> Self::Sys;
+
+    /// Convert into a [`System`], or return an error if the parameters of the system have
+    /// conflicting [borrows][SystemParam::borrows], which would make it panic when it is run.
+    fn try_system(self) -> Result<Self::Sys, ConflictingBorrowsError>
+    where
+        Self: Sized,
+    {
+        Ok(self.system())
+    }
 }
 
 impl<T, In, Out> IntoSystem<T, In, Out> for T
@@ -69,7 +657,14 @@ where
 /// other custom ways to access the data inside a [`World`].
 pub trait SystemParam: Sized {
     /// The intermediate state for the parameter, that may be extracted from the world.
-    type State;
+    ///
+    /// Systems keep the state of their parameters between runs, and systems must be [`Send`] and
+    /// [`Sync`], so the state must be too.
+    ///
+    /// > **Note:** This bound was added when systems started keeping their parameter state. A
+    /// > parameter with a state that isn't [`Send`] and [`Sync`] has to wrap it, for instance in a
+    /// > `Mutex`.
+    type State: Send + Sync + 'static;
     /// The type of the parameter, ranging over the lifetime of the intermediate state.
     ///
     /// > **ℹ️ Important:** This type must be the same type as `Self`, other than the fact that it
@@ -80,9 +675,55 @@ pub trait SystemParam: Sized {
     type Param<'s>;
     /// This is called to produce the intermediate state of the system parameter.
     ///
-    /// This state will be created immediately before the system is run, and will kept alive until
-    /// the system is done running.
+    /// Systems created with [`IntoSystem`] create the state the first time that they are run, and
+    /// keep it for the following runs, see [`update_state()`][Self::update_state] and
+    /// [`change_world()`][Self::change_world].
     fn get_state(world: &World) -> Self::State;
+    /// This is called to produce the intermediate state of the parameter when it is used as an
+    /// argument to the system with the given [`SystemId`].
+    ///
+    /// This may be implemented by parameters that need to keep per-system data in the world, such
+    /// as the read cursor of an [`EventReader`]. By default it just calls
+    /// [`get_state()`][Self::get_state].
+    fn get_system_state(world: &World, system: SystemId) -> Self::State {
+        let _ = system;
+        Self::get_state(world)
+    }
+    /// This is called before the system is run again on the same world, to re-validate the state
+    /// kept from the previous run.
+    ///
+    /// The resource and component cells of a world never change, so by default this does nothing.
+    /// Parameters that initialize resources override it to initialize them again if they have been
+    /// removed since the last run.
+    fn update_state(world: &World, state: &mut Self::State) {
+        let _ = (world, state);
+    }
+    /// This is called before the system is run on a different world than the last time, such as a
+    /// clone of the world, to update the state kept from the previous run.
+    ///
+    /// By default the state is replaced with a new one from
+    /// [`get_system_state()`][Self::get_system_state]. Parameters with state that isn't tied to the
+    /// world, like [`Local`], override it to keep the state instead.
+    fn change_world(world: &World, system: SystemId, state: &mut Self::State) {
+        *state = Self::get_system_state(world, system);
+    }
+    /// Report the data in the world that this parameter accesses.
+    ///
+    /// This is used to decide which systems may run in parallel. By default the parameter is
+    /// assumed to access the whole world, so systems using it never run in parallel with others.
+    fn access(access: &mut SystemAccess) {
+        access.world = true;
+    }
+    /// Report the resources and component stores that this parameter keeps borrowed while the
+    /// system runs.
+    ///
+    /// This is used to reject systems with parameters that can't be borrowed at the same time, see
+    /// [`IntoSystem::try_system()`]. By default it is the same as [`access()`][Self::access], but
+    /// parameters that borrow their data differently than they access it, like [`ResInit`], which
+    /// only writes to its resource to initialize it, override it.
+    fn borrows(borrows: &mut SystemAccess) {
+        Self::access(borrows);
+    }
     /// This is used create an instance of the system parame, possibly borrowed from the
     /// intermediate parameter state.
     #[allow(clippy::needless_lifetimes)] // Explicit lifetimes help clarity in this case
@@ -93,6 +734,9 @@ impl SystemParam for &'_ World {
     type State = ();
     type Param<'s> = &'s World;
     fn get_state(_world: &World) -> Self::State {}
+    fn access(access: &mut SystemAccess) {
+        access.world = true;
+    }
     fn borrow<'s>(world: &'s World, _state: &'s mut Self::State) -> Self::Param<'s> {
         world
     }
@@ -102,6 +746,45 @@ impl SystemParam for &'_ World {
 #[derive(Deref, DerefMut)]
 pub struct In<T>(pub T);
 
+/// [`SystemParam`] for data that is private to a system and kept between its runs.
+///
+/// The value is created with [`Default`] the first time the system runs. It is stored in the system,
+/// not the [`World`], so it is kept when the system is run on a different world, such as a snapshot.
+///
+/// > **⚠️ Warning:** Because it isn't part of the world, a [`Local`] is **not** saved and restored
+/// > with world snapshots, so it isn't rolled back by GGRS. Systems that run in a rollback
+/// > session should keep any state that affects the simulation in resources or components
+/// > instead.
+pub struct Local<'a, T: Default + Send + Sync + 'static>(&'a mut T);
+impl<'a, T: Default + Send + Sync + 'static> std::ops::Deref for Local<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+impl<'a, T: Default + Send + Sync + 'static> std::ops::DerefMut for Local<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+impl<'a, T: Default + Send + Sync + 'static> SystemParam for Local<'a, T> {
+    type State = T;
+    type Param<'p> = Local<'p, T>;
+
+    fn get_state(_world: &World) -> Self::State {
+        T::default()
+    }
+
+    fn change_world(_world: &World, _system: SystemId, _state: &mut Self::State) {}
+
+    fn access(_access: &mut SystemAccess) {}
+
+    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        Local(state)
+    }
+}
+
 /// [`SystemParam`] for getting read access to a resource.
 ///
 /// Use [`ResInit`] if you want to automatically initialize the resource.
@@ -127,8 +810,10 @@ impl<'a, T: HasSchema + FromWorld> std::ops::Deref for ResInit<'a, T> {
 
 /// [`SystemParam`] for getting mutable access to a resource.
 ///
+/// The resource is only marked as changed when it is mutably dereferenced.
+///
 /// Use [`ResMutInit`] if you want to automatically initialize the resource.
-pub struct ResMut<'a, T: HasSchema>(RefMut<'a, T>);
+pub struct ResMut<'a, T: HasSchema>(RefMut<'a, T>, &'a UntypedResource);
 impl<'a, T: HasSchema> std::ops::Deref for ResMut<'a, T> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
@@ -137,6 +822,7 @@ impl<'a, T: HasSchema> std::ops::Deref for ResMut<'a, T> {
 }
 impl<'a, T: HasSchema> std::ops::DerefMut for ResMut<'a, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        self.1.mark_changed();
         &mut self.0
     }
 }
@@ -144,8 +830,10 @@ impl<'a, T: HasSchema> std::ops::DerefMut for ResMut<'a, T> {
 /// [`SystemParam`] for getting mutable access to a resource and initializing it if it doesn't
 /// already exist.
 ///
+/// The resource is only marked as changed when it is mutably dereferenced.
+///
 /// Use [`ResMut`] if you don't want to automatically initialize the resource.
-pub struct ResMutInit<'a, T: HasSchema + FromWorld>(RefMut<'a, T>);
+pub struct ResMutInit<'a, T: HasSchema + FromWorld>(RefMut<'a, T>, &'a UntypedResource);
 impl<'a, T: HasSchema + FromWorld> std::ops::Deref for ResMutInit<'a, T> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
@@ -154,6 +842,7 @@ impl<'a, T: HasSchema + FromWorld> std::ops::Deref for ResMutInit<'a, T> {
 }
 impl<'a, T: HasSchema + FromWorld> std::ops::DerefMut for ResMutInit<'a, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        self.1.mark_changed();
         &mut self.0
     }
 }
@@ -166,6 +855,10 @@ impl<'a, T: HasSchema> SystemParam for Res<'a, T> {
         world.resources.get_cell::<T>()
     }
 
+    fn access(access: &mut SystemAccess) {
+        access.read_resource(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         Res(state.borrow().unwrap_or_else(|| {
             panic!(
@@ -187,6 +880,10 @@ impl<'a, T: HasSchema> SystemParam for Option<Res<'a, T>> {
         world.resources.get_cell::<T>()
     }
 
+    fn access(access: &mut SystemAccess) {
+        access.read_resource(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         state.borrow().map(|x| Res(x))
     }
@@ -202,6 +899,19 @@ impl<'a, T: HasSchema + FromWorld> SystemParam for ResInit<'a, T> {
         cell
     }
 
+    fn update_state(world: &World, state: &mut Self::State) {
+        state.init(world);
+    }
+
+    fn access(access: &mut SystemAccess) {
+        // Initializing the resource may need to write to it.
+        access.write_resource(T::schema());
+    }
+
+    fn borrows(borrows: &mut SystemAccess) {
+        borrows.read_resource(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         ResInit(state.borrow().unwrap())
     }
@@ -215,8 +925,12 @@ impl<'a, T: HasSchema> SystemParam for ResMut<'a, T> {
         world.resources.get_cell::<T>()
     }
 
+    fn access(access: &mut SystemAccess) {
+        access.write_resource(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        ResMut(state.borrow_mut().unwrap_or_else(|| {
+        let data = state.borrow_mut_untracked().unwrap_or_else(|| {
             panic!(
                 "Resource of type `{}` not in world. \
                 You may need to insert or initialize the resource or use \
@@ -224,7 +938,8 @@ impl<'a, T: HasSchema> SystemParam for ResMut<'a, T> {
                 resource with the default value.",
                 std::any::type_name::<T>()
             )
-        }))
+        });
+        ResMut(data, state.untyped())
     }
 }
 
@@ -236,8 +951,15 @@ impl<'a, T: HasSchema> SystemParam for Option<ResMut<'a, T>> {
         world.resources.get_cell::<T>()
     }
 
+    fn access(access: &mut SystemAccess) {
+        access.write_resource(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        state.borrow_mut().map(|state| ResMut(state))
+        let untyped = state.untyped();
+        state
+            .borrow_mut_untracked()
+            .map(|data| ResMut(data, untyped))
     }
 }
 
@@ -251,8 +973,16 @@ impl<'a, T: HasSchema + FromWorld> SystemParam for ResMutInit<'a, T> {
         cell
     }
 
+    fn update_state(world: &World, state: &mut Self::State) {
+        state.init(world);
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.write_resource(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
-        ResMutInit(state.borrow_mut().unwrap())
+        ResMutInit(state.borrow_mut_untracked().unwrap(), state.untyped())
     }
 }
 
@@ -269,6 +999,10 @@ impl<'a, T: HasSchema> SystemParam for Comp<'a, T> {
         world.components.get_cell::<T>()
     }
 
+    fn access(access: &mut SystemAccess) {
+        access.read_component(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         state.borrow()
     }
@@ -282,11 +1016,72 @@ impl<'a, T: HasSchema> SystemParam for CompMut<'a, T> {
         world.components.get_cell::<T>()
     }
 
+    fn access(access: &mut SystemAccess) {
+        access.write_component(T::schema());
+    }
+
     fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
         state.borrow_mut()
     }
 }
 
+/// [`SystemParam`] for reading the entities that had a component removed, either explicitly or
+/// because the entity was killed, since the system last ran.
+///
+/// Like an [`EventReader`], each system keeps its own read cursor, so it will only see each removal
+/// once. Removals are kept for two updates, so a system has to run at least once every
+/// [`World::maintain()`] to see all of them.
+pub struct RemovedComponents<'a, T: HasSchema> {
+    store: Ref<'a, ComponentStore<T>>,
+    system: SystemId,
+}
+
+impl<'a, T: HasSchema> RemovedComponents<'a, T> {
+    /// Iterate over the entities that had the component removed since this system last read them.
+    pub fn read(&mut self) -> impl Iterator<Item = Entity> + '_ {
+        self.store.removed().read(self.system).copied()
+    }
+
+    /// Get the number of removals this system has not read yet.
+    pub fn len(&self) -> usize {
+        self.store.removed().unread_len(self.system)
+    }
+
+    /// Whether or not there are no removals for this system to read.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Mark all of the pending removals as read, without iterating over them.
+    pub fn clear(&mut self) {
+        self.store.removed().mark_read(self.system);
+    }
+}
+
+impl<'a, T: HasSchema> SystemParam for RemovedComponents<'a, T> {
+    type State = (Arc<AtomicCell<ComponentStore<T>>>, SystemId);
+    type Param<'p> = RemovedComponents<'p, T>;
+
+    fn get_state(world: &World) -> Self::State {
+        Self::get_system_state(world, SystemId::new_unique())
+    }
+
+    fn get_system_state(world: &World, system: SystemId) -> Self::State {
+        (world.components.get_cell::<T>(), system)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.read_component(T::schema());
+    }
+
+    fn borrow<'s>(_world: &'s World, state: &'s mut Self::State) -> Self::Param<'s> {
+        RemovedComponents {
+            store: state.0.borrow(),
+            system: state.1,
+        }
+    }
+}
+
 macro_rules! impl_system {
     ($($args:ident,)*) => {
         #[allow(unused_parens)]
@@ -307,25 +1102,78 @@ macro_rules! impl_system {
                 $(
                     $args,
                 )*
-            ) -> Out
+            ) -> Out,
         {
             type Sys = StaticSystem<(), Out>;
-            fn system(mut self) -> Self::Sys {
-                StaticSystem {
+            fn system(self) -> Self::Sys {
+                self.try_system().unwrap_or_else(|error| panic!("{error}"))
+            }
+
+            fn try_system(mut self) -> Result<Self::Sys, ConflictingBorrowsError> {
+                #[allow(unused_mut)]
+                let mut borrows = SystemAccess::default();
+                $(
+                    $args::borrows(&mut borrows);
+                )*
+                if !borrows.conflicts.is_empty() {
+                    return Err(ConflictingBorrowsError {
+                        system: std::any::type_name::<F>(),
+                        conflicts: borrows.conflicts,
+                    });
+                }
+
+                let _system_id = SystemId::new_unique();
+                // The parameter states are kept between runs, along with the world they are for.
+                let mut state: Option<(WorldId, ($($args::State,)*))> = None;
+                Ok(StaticSystem {
                     name: std::any::type_name::<F>(),
-                    run: Box::new(move |_world, _input| {
+                    ordering: default(),
+                    run_conditions: default(),
+                    access: {
+                        #[allow(unused_mut)]
+                        let mut access = SystemAccess::default();
                         $(
-                            #[allow(non_snake_case)]
-                            let mut $args = $args::get_state(_world);
+                            $args::access(&mut access);
                         )*
+                        access
+                    },
+                    run: Box::new(move |_world, _input| {
+                        match &mut state {
+                            Some((world_id, state)) if *world_id == _world.id() => {
+                                #[allow(non_snake_case)]
+                                let ($($args,)*) = state;
+                                $(
+                                    $args::update_state(_world, $args);
+                                )*
+                            }
+                            Some((world_id, state)) => {
+                                *world_id = _world.id();
+                                #[allow(non_snake_case)]
+                                let ($($args,)*) = state;
+                                $(
+                                    $args::change_world(_world, _system_id, $args);
+                                )*
+                            }
+                            None => {
+                                state = Some((
+                                    _world.id(),
+                                    ($($args::get_system_state(_world, _system_id),)*),
+                                ));
+                            }
+                        }
+                        #[allow(non_snake_case)]
+                        let ($($args,)*) = &mut state.as_mut().unwrap().1;
 
-                        self(
-                            $(
-                                $args::borrow(_world, &mut $args),
-                            )*
-                        )
+                        let ticks = _world.components.ticks.start_system(_system_id);
+                        ticks.scope(|| {
+                            self(
+                                $(
+                                    $args::borrow(_world, $args),
+                                )*
+                            )
+                        })
                     })
-                }
+                })
             }
         }
     };
@@ -355,26 +1203,79 @@ macro_rules! impl_system_with_input {
                 $(
                     $args,
                 )*
-            ) -> Out
+            ) -> Out,
         {
             type Sys = StaticSystem<InT, Out>;
-            fn system(mut self) -> Self::Sys {
-                StaticSystem {
+            fn system(self) -> Self::Sys {
+                self.try_system().unwrap_or_else(|error| panic!("{error}"))
+            }
+
+            fn try_system(mut self) -> Result<Self::Sys, ConflictingBorrowsError> {
+                #[allow(unused_mut)]
+                let mut borrows = SystemAccess::default();
+                $(
+                    $args::borrows(&mut borrows);
+                )*
+                if !borrows.conflicts.is_empty() {
+                    return Err(ConflictingBorrowsError {
+                        system: std::any::type_name::<F>(),
+                        conflicts: borrows.conflicts,
+                    });
+                }
+
+                let _system_id = SystemId::new_unique();
+                // The parameter states are kept between runs, along with the world they are for.
+                let mut state: Option<(WorldId, ($($args::State,)*))> = None;
+                Ok(StaticSystem {
                     name: std::any::type_name::<F>(),
-                    run: Box::new(move |_world, input| {
+                    ordering: default(),
+                    run_conditions: default(),
+                    access: {
+                        #[allow(unused_mut)]
+                        let mut access = SystemAccess::default();
                         $(
-                            #[allow(non_snake_case)]
-                            let mut $args = $args::get_state(_world);
+                            $args::access(&mut access);
                         )*
+                        access
+                    },
+                    run: Box::new(move |_world, input| {
+                        match &mut state {
+                            Some((world_id, state)) if *world_id == _world.id() => {
+                                #[allow(non_snake_case)]
+                                let ($($args,)*) = state;
+                                $(
+                                    $args::update_state(_world, $args);
+                                )*
+                            }
+                            Some((world_id, state)) => {
+                                *world_id = _world.id();
+                                #[allow(non_snake_case)]
+                                let ($($args,)*) = state;
+                                $(
+                                    $args::change_world(_world, _system_id, $args);
+                                )*
+                            }
+                            None => {
+                                state = Some((
+                                    _world.id(),
+                                    ($($args::get_system_state(_world, _system_id),)*),
+                                ));
+                            }
+                        }
+                        #[allow(non_snake_case)]
+                        let ($($args,)*) = &mut state.as_mut().unwrap().1;
 
-                        self(
-                            In(input),
-                            $(
-                                $args::borrow(_world, &mut $args),
-                            )*
-                        )
+                        let ticks = _world.components.ticks.start_system(_system_id);
+                        ticks.scope(|| {
+                            self(
+                                In(input),
+                                $(
+                                    $args::borrow(_world, $args),
+                                )*
+                            )
+                        })
                     })
-                }
+                })
             }
         }
     };
@@ -410,8 +1311,8 @@ mod tests {
         ) -> u32 {
             0
         }
-        // Technically reusing the same type is incorrect and causes a runtime panic.
-        // However, there doesn't seem to be a clean way to handle type inequality in generics.
+        // Reusing the same type mutably would panic when the system is run, so it is rejected when
+        // the system is built.
         #[allow(clippy::too_many_arguments)]
         fn tmp2(
             _var7: Comp<i64>,
@@ -430,10 +1331,47 @@ mod tests {
         }
         fn tmp3(_in: In<usize>, _comp1: Comp<i64>) {}
         let _ = tmp.system();
-        let _ = tmp2.system();
+        assert!(tmp2.try_system().is_err());
         let _ = tmp3.system();
     }
 
+    #[derive(HasSchema, Clone, Default)]
+    struct Pos;
+
+    #[test]
+    fn conflicting_borrows() {
+        fn conflicting(_a: Comp<Pos>, _b: Res<u32>, _c: CompMut<Pos>, _d: ResMutInit<u32>) {}
+        let error = conflicting.try_system().err().unwrap();
+        assert!(error.system.ends_with("conflicting"));
+        assert_eq!(
+            error.conflicts,
+            [
+                BorrowConflict::Component(Pos::schema()),
+                BorrowConflict::Resource(u32::schema())
+            ]
+        );
+
+        // Parameters that only read don't conflict.
+        fn reads(_a: Comp<Pos>, _b: Comp<Pos>, _c: Res<u32>, _d: ResInit<u32>) {}
+        fn spawns(_commands: Commands, _entities: Res<Entities>) {}
+        assert!(reads.try_system().is_ok());
+        assert!(spawns.try_system().is_ok());
+
+        // Spawning borrows the entities, so it can't be done while they are borrowed mutably.
+        fn spawns_mut(_commands: Commands, _entities: ResMut<Entities>) {}
+        assert_eq!(
+            spawns_mut.try_system().err().unwrap().conflicts,
+            [BorrowConflict::Resource(Entities::schema())]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "has parameters with conflicting borrows of resource")]
+    fn conflicting_borrows_panic() {
+        fn conflicting(_a: ResMut<u32>, _b: ResMut<u32>) {}
+        conflicting.system();
+    }
+
     #[test]
     fn system_is_send() {
         let x = 6;
@@ -514,4 +1452,110 @@ mod tests {
         let res = world.resource::<A>();
         assert_eq!(*res, A);
     }
+
+    #[test]
+    fn removed_components() {
+        #[derive(HasSchema, Clone, Default)]
+        struct Removed {
+            entities: Vec<Entity>,
+        }
+
+        let mut world = World::new();
+        let [a, b] = world.run_system(
+            |mut entities: ResMut<Entities>, mut store: CompMut<u32>| {
+                let [a, b] = std::array::from_fn(|_| entities.create());
+                store.insert(a, 1);
+                store.insert(b, 2);
+                [a, b]
+            },
+            (),
+        );
+
+        let mut stages = SystemStages::with_core_stages();
+        stages.add_system_to_stage(
+            First,
+            |mut removed: RemovedComponents<u32>, mut log: ResMutInit<Removed>| {
+                log.entities.extend(removed.read());
+            },
+        );
+        stages.run(&mut world);
+        assert!(world.resource::<Removed>().entities.is_empty());
+
+        // Explicit removals and removals by killing are both seen, once.
+        world.components.get::<u32>().borrow_mut().remove(a);
+        world.resource_mut::<Entities>().kill(b);
+        world.maintain();
+        stages.run(&mut world);
+        stages.run(&mut world);
+        assert_eq!(world.resource::<Removed>().entities, [a, b]);
+    }
+
+    #[test]
+    fn local_state() {
+        fn count(mut counter: Local<u32>, mut total: ResMutInit<u32>) -> u32 {
+            *counter += 1;
+            *total += 1;
+            *counter
+        }
+
+        let world = World::new();
+        let mut system = count.system();
+        assert_eq!(system.run(&world, ()), 1);
+        assert_eq!(system.run(&world, ()), 2);
+
+        // The local state is kept when running on another world, but the other parameters are
+        // looked up again.
+        let snapshot = world.clone();
+        assert_eq!(system.run(&snapshot, ()), 3);
+        assert_eq!(*snapshot.resource::<u32>(), 3);
+        assert_eq!(*world.resource::<u32>(), 2);
+
+        // Resources are initialized again if they were removed between runs.
+        world.resources.remove::<u32>();
+        assert_eq!(system.run(&world, ()), 4);
+        assert_eq!(*world.resource::<u32>(), 1);
+
+        // Every system has its own local state.
+        assert_eq!(world.run_system(count, ()), 1);
+    }
+
+    #[test]
+    fn local_after_loading_snapshot() {
+        fn count(
+            mut counter: Local<u32>,
+            mut total: ResMutInit<u32>,
+            entities: Res<Entities>,
+            pos: Comp<Pos>,
+        ) -> (u32, u32, usize) {
+            *counter += 1;
+            *total += 1;
+            let changed = entities.iter_with(Changed(&pos)).count();
+            (*counter, *total, changed)
+        }
+        fn spawn(mut entities: ResMut<Entities>, mut pos: CompMut<Pos>) -> Entity {
+            let entity = entities.create();
+            pos.insert(entity, Pos);
+            entity
+        }
+
+        let world = World::new();
+        let entity = world.run_system(spawn, ());
+        let mut system = count.system();
+        assert_eq!(system.run(&world, ()), (1, 1, 1));
+
+        let snapshot = world.clone();
+        world.run_system(
+            move |mut pos: CompMut<Pos>| {
+                pos.get_mut(entity);
+            },
+            (),
+        );
+        assert_eq!(system.run(&world, ()), (2, 2, 1));
+
+        // Loading the snapshot, like a GGRS rollback, restores the resources and what the system
+        // has seen changed, but the local keeps the value from the latest run.
+        let loaded = snapshot.clone();
+        assert_eq!(system.run(&loaded, ()), (3, 2, 0));
+        assert_eq!(system.run(&loaded, ()), (4, 3, 0));
+    }
 }
@@ -0,0 +1,251 @@
+//! Per-system timing instrumentation.
+//!
+//! When [`SystemStages::timings`] is set, the [`SystemStages`] and the built-in stages measure how
+//! long every system and every stage takes to run, and record it in the [`SystemTimings`]:
+//!
+//! ```
+//! # use bones_ecs::prelude::*;
+//! # fn movement() {}
+//! let mut world = World::new();
+//! let mut stages = SystemStages::with_core_stages();
+//! stages.add_system_to_stage(Update, movement);
+//!
+//! stages.timings = Some(SystemTimings::default());
+//! stages.run(&mut world);
+//!
+//! let timings = stages.timings.as_ref().unwrap();
+//! for (name, stats) in timings.slowest_systems().iter().take(5) {
+//!     println!("{name}: {:?}", stats.average);
+//! }
+//! ```
+//!
+//! Nothing is measured if there are no [`SystemTimings`]. They are kept out of the [`World`], so
+//! that they aren't copied into snapshots of the world, and so that recording them doesn't copy
+//! the world's resources on write. Independently of that, if the
+//! `tracing` feature is enabled, every system and stage is run inside of a [`tracing`] span, so
+//! that they show up in profilers.
+//!
+//! [`tracing`]: https://docs.rs/tracing
+
+use std::cell::RefCell;
+
+use instant::{Duration, Instant};
+
+use crate::prelude::*;
+
+/// The number of runs that [`TimingStats::average`] is averaged over.
+pub const TIMING_WINDOW: u32 = 60;
+
+/// Rolling timing statistics for a system or a stage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimingStats {
+    /// How long the last run took.
+    pub last: Duration,
+    /// The average duration of the last [`TIMING_WINDOW`] runs.
+    ///
+    /// This is an exponential moving average, so older runs have less weight.
+    pub average: Duration,
+    /// The longest run since the stats were reset.
+    pub max: Duration,
+    /// The number of runs since the stats were reset.
+    pub runs: u32,
+}
+
+impl TimingStats {
+    /// Record the duration of a run.
+    pub fn record(&mut self, duration: Duration) {
+        self.runs = self.runs.saturating_add(1);
+        let window = self.runs.min(TIMING_WINDOW);
+        self.average = if duration > self.average {
+            self.average + (duration - self.average) / window
+        } else {
+            self.average - (self.average - duration) / window
+        };
+        self.last = duration;
+        self.max = self.max.max(duration);
+    }
+}
+
+/// The [`TimingStats`] of the systems and stages of some [`SystemStages`].
+///
+/// See the [module documentation][self].
+#[derive(Clone, Debug, Default)]
+pub struct SystemTimings {
+    /// The stats of every system that has run, by system name.
+    ///
+    /// Systems with the same name, like a system that was added to several stages, share their
+    /// stats.
+    pub systems: UstrMap<TimingStats>,
+    /// The stats of every stage that has run, by stage name.
+    ///
+    /// The time of a stage includes its run conditions, its [`Commands`] and its exclusive
+    /// systems.
+    pub stages: UstrMap<TimingStats>,
+}
+
+impl SystemTimings {
+    /// Record the duration of a run of the system with the given name.
+    pub fn record_system(&mut self, name: &str, duration: Duration) {
+        self.systems.entry(ustr(name)).or_default().record(duration);
+    }
+
+    /// Record the duration of a run of the stage with the given name.
+    pub fn record_stage(&mut self, name: &str, duration: Duration) {
+        self.stages.entry(ustr(name)).or_default().record(duration);
+    }
+
+    /// Get the stats of the systems, sorted from the slowest to the fastest on average.
+    pub fn slowest_systems(&self) -> Vec<(Ustr, TimingStats)> {
+        let mut systems = self
+            .systems
+            .iter()
+            .map(|(&name, &stats)| (name, stats))
+            .collect::<Vec<_>>();
+        systems.sort_by(|a, b| b.1.average.cmp(&a.1.average));
+        systems
+    }
+
+    /// Forget all of the recorded stats.
+    pub fn reset(&mut self) {
+        self.systems.clear();
+        self.stages.clear();
+    }
+}
+
+thread_local! {
+    /// The timings of the [`SystemStages`] being run on the current thread.
+    static CURRENT_TIMINGS: RefCell<Option<SystemTimings>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with the given timings as the timings of the current thread, so that the systems and
+/// stages that it runs are timed and recorded in them if they are [`Some`].
+pub(crate) fn with_timings<R>(timings: &mut Option<SystemTimings>, f: impl FnOnce() -> R) -> R {
+    /// Moves the timings back and restores the previous ones when dropped, even if a system
+    /// panics.
+    struct Restore<'a> {
+        timings: &'a mut Option<SystemTimings>,
+        previous: Option<SystemTimings>,
+    }
+    impl Drop for Restore<'_> {
+        fn drop(&mut self) {
+            let previous = self.previous.take();
+            *self.timings = CURRENT_TIMINGS.with(|timings| timings.replace(previous));
+        }
+    }
+    let previous = CURRENT_TIMINGS.with(|current| current.replace(timings.take()));
+    let _restore = Restore { timings, previous };
+    f()
+}
+
+/// Whether or not the systems and stages run on the current thread should be timed.
+pub(crate) fn is_timed() -> bool {
+    CURRENT_TIMINGS.with(|timings| timings.borrow().is_some())
+}
+
+/// Run a system, inside of a `tracing` span if the feature is enabled, returning how long it took
+/// if `timed` is `true`.
+#[inline]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn instrument_system<R>(
+    name: &str,
+    timed: bool,
+    run: impl FnOnce() -> R,
+) -> (R, Option<Duration>) {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("system", name).entered();
+    let start = timed.then(Instant::now);
+    let out = run();
+    (out, start.map(|start| start.elapsed()))
+}
+
+/// Run a stage, inside of a `tracing` span if the feature is enabled, returning how long it took
+/// if `timed` is `true`.
+#[inline]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn instrument_stage<R>(
+    name: &str,
+    timed: bool,
+    run: impl FnOnce() -> R,
+) -> (R, Option<Duration>) {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("stage", name).entered();
+    let start = timed.then(Instant::now);
+    let out = run();
+    (out, start.map(|start| start.elapsed()))
+}
+
+/// Record the duration of a system run in the current [`SystemTimings`], if it was timed.
+pub(crate) fn record_system(name: &str, duration: Option<Duration>) {
+    if let Some(duration) = duration {
+        CURRENT_TIMINGS.with(|timings| {
+            if let Some(timings) = &mut *timings.borrow_mut() {
+                timings.record_system(name, duration);
+            }
+        });
+    }
+}
+
+/// Record the duration of a stage run in the current [`SystemTimings`], if it was timed.
+pub(crate) fn record_stage(name: &str, duration: Option<Duration>) {
+    if let Some(duration) = duration {
+        CURRENT_TIMINGS.with(|timings| {
+            if let Some(timings) = &mut *timings.borrow_mut() {
+                timings.record_stage(name, duration);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn timing_stats() {
+        let mut stats = TimingStats::default();
+        stats.record(Duration::from_millis(4));
+        stats.record(Duration::from_millis(2));
+        assert_eq!(stats.last, Duration::from_millis(2));
+        assert_eq!(stats.average, Duration::from_millis(3));
+        assert_eq!(stats.max, Duration::from_millis(4));
+
+        // The average only follows the last runs.
+        for _ in 0..1000 {
+            stats.record(Duration::from_millis(10));
+        }
+        assert!(stats.average > Duration::from_micros(9_990));
+        assert_eq!(stats.runs, 1002);
+    }
+
+    fn slow_system() {
+        std::thread::sleep(Duration::from_millis(2));
+    }
+
+    #[test]
+    fn system_timings() {
+        let mut world = World::new();
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .add_system_to_stage(Update, slow_system)
+            .add_system_to_stage(PostUpdate, || ());
+
+        // Nothing is recorded unless the timings are enabled.
+        stages.run(&mut world);
+        assert!(stages.timings.is_none());
+
+        stages.timings = Some(SystemTimings::default());
+        stages.run(&mut world);
+        stages.run(&mut world);
+
+        let timings = stages.timings.as_ref().unwrap();
+        let slowest = timings.slowest_systems();
+        assert_eq!(slowest.len(), 2);
+        assert!(slowest[0].0.ends_with("slow_system"));
+        assert_eq!(slowest[0].1.runs, 2);
+        assert!(slowest[0].1.average >= Duration::from_millis(2));
+        assert!(timings.stages[&ustr("Update")].average >= Duration::from_millis(2));
+        assert_eq!(timings.stages[&ustr("First")].runs, 2);
+    }
+}
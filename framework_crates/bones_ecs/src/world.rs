@@ -1,6 +1,26 @@
 //! Contains the ECS [`World`].
 
-use crate::prelude::*;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use crate::{prelude::*, system::ChangeTicks};
+
+/// A unique identifier for a [`World`].
+///
+/// Every world gets a new ID when it is created or cloned, so that systems can tell when they are
+/// run on a different world than the last time, and must look up their parameters again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WorldId(u64);
+
+impl WorldId {
+    /// Allocate a new, unique [`WorldId`].
+    fn new_unique() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
 
 /// The [`World`] is simply a collection of [`Resources`], and [`ComponentStores`].
 ///
@@ -9,12 +29,21 @@ use crate::prelude::*;
 /// [`World`] is designed to be trivially [`Clone`]ed to allow for snapshotting the world state. The
 /// is especially useful in the context of rollback networking, which requires the ability to
 /// snapshot and restore state.
-#[derive(Clone)]
 pub struct World {
     /// Stores the world resources.
     pub resources: Resources,
     /// Stores the world components.
     pub components: ComponentStores,
+    id: WorldId,
+}
+impl Clone for World {
+    fn clone(&self) -> Self {
+        Self {
+            resources: self.resources.clone(),
+            components: self.components.clone(),
+            id: WorldId::new_unique(),
+        }
+    }
 }
 impl std::fmt::Debug for World {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -32,6 +61,7 @@ impl Default for World {
         Self {
             resources,
             components: Default::default(),
+            id: WorldId::new_unique(),
         }
     }
 }
@@ -54,17 +84,39 @@ impl World {
         World {
             resources,
             components: default(),
+            id: WorldId::new_unique(),
         }
     }
 
+    /// Get the unique ID of the world.
+    ///
+    /// Clones of the world get a different ID.
+    pub fn id(&self) -> WorldId {
+        self.id
+    }
+
     /// Remove the component info for dead entities.
     ///
     /// This should be called every game frame to cleanup entities that have been killed.
     ///
     /// This will remove the component storage for all killed entities, and allow their slots to be
     /// re-used for any new entities.
+    ///
+    /// Killed entities are also removed from the entity [`relation`][crate::relation]s, which may
+    /// kill more entities, and from the entity [`hierarchy`][crate::hierarchy]. The
+    /// [reserved][Commands::spawn] entities are made alive, the removed components of the
+    /// previous update are dropped, and the empty chunks at the end of the entity and component
+    /// bitsets are released.
+    ///
+    /// The [component hooks][crate::hooks] for the removed components aren't run here, but at the
+    /// next stage boundary, so no [`Commands`] are applied while maintaining the world.
     pub fn maintain(&self) {
         let mut entities = self.resources.get_mut::<Entities>().unwrap();
+        entities.flush();
+        crate::relation::remove_killed(&self.resources, &mut entities);
+        if !entities.killed().is_empty() {
+            crate::hierarchy::remove_killed(&self.components, entities.killed());
+        }
         for components in self.components.components.read_only_view().values() {
             let mut components = components.borrow_mut();
             let killed = entities.killed();
@@ -75,8 +127,75 @@ impl World {
                     components.remove_raw(entity, None);
                 }
             }
+            components.update();
+            components.shrink();
         }
         entities.clear_killed();
+        entities.shrink();
+        entities.changes.update();
+    }
+
+    /// Swap the buffers of every [`Events`] resource in the world, dropping events that have
+    /// already been available for a full update.
+    ///
+    /// This is called automatically at the end of [`SystemStages::run()`].
+    pub fn update_events(&self) {
+        for cell in self.resources.untyped().resources.read_only_view().values() {
+            let Some(update) = cell.schema().type_data.get::<EventsUpdate>() else {
+                continue;
+            };
+            if let Some(events) = cell.borrow_mut().as_mut() {
+                (update.update_fn)(events.as_mut());
+            }
+        }
+    }
+
+    /// Add a hook that is run when a component with the given schema is inserted, replaced or
+    /// removed.
+    ///
+    /// See the [`hooks`][crate::hooks] module.
+    pub fn add_component_hook(
+        &self,
+        schema: &'static Schema,
+        kind: ComponentHookKind,
+        hook: ComponentHook,
+    ) {
+        self.components
+            .get_by_schema(schema)
+            .borrow_mut()
+            .add_hook(kind, hook);
+    }
+
+    /// Add a hook that is run when a `T` component is inserted for an entity that didn't have one.
+    pub fn on_insert<T: HasSchema>(
+        &self,
+        hook: impl Fn(&mut Commands, Entity) + Sync + Send + 'static,
+    ) {
+        self.add_component_hook(T::schema(), ComponentHookKind::Insert, Arc::new(hook));
+    }
+
+    /// Add a hook that is run when the `T` component of an entity is replaced.
+    pub fn on_replace<T: HasSchema>(
+        &self,
+        hook: impl Fn(&mut Commands, Entity) + Sync + Send + 'static,
+    ) {
+        self.add_component_hook(T::schema(), ComponentHookKind::Replace, Arc::new(hook));
+    }
+
+    /// Add a hook that is run when the `T` component of an entity is removed, including when the
+    /// entity is killed.
+    pub fn on_remove<T: HasSchema>(
+        &self,
+        hook: impl Fn(&mut Commands, Entity) + Sync + Send + 'static,
+    ) {
+        self.add_component_hook(T::schema(), ComponentHookKind::Remove, Arc::new(hook));
+    }
+
+    /// Compare this world with a `new` one, returning the structural differences between them.
+    ///
+    /// See [`WorldDiff`].
+    pub fn diff(&self, new: &World) -> WorldDiff {
+        WorldDiff::new(self, new)
     }
 
     /// Run a system once.
@@ -90,7 +209,7 @@ impl World {
         S::Sys: 'system,
     {
         let mut s = system.system();
-        s.run(self, input)
+        ChangeTicks::one_shot(|| s.run(self, input))
     }
 
     /// Initialize a resource of type `T` by inserting it's default value.
@@ -289,6 +408,32 @@ mod tests {
         world1.run_system(test_pos_vel_1_run, ());
     }
 
+    #[test]
+    fn snapshot_is_copy_on_write() {
+        let mut world = World::new();
+        world.run_system(setup_world, ());
+        world.insert_resource(Vel(1, 2));
+        let snap = world.clone();
+
+        let shares_data = |a: &World, b: &World| {
+            let a = a.components.get_by_schema(Pos::schema()).borrow();
+            let b = b.components.get_by_schema(Pos::schema()).borrow();
+            std::sync::Arc::ptr_eq(&a.data, &b.data)
+        };
+
+        // Reading from the world doesn't copy the data.
+        world.run_system(test_after_setup_state, ());
+        assert!(shares_data(&world, &snap));
+
+        // Modifying the world copies the modified component store and resource only.
+        world.run_system(pos_vel_system, ());
+        world.resource_mut::<Vel>().0 = 3;
+        assert!(!shares_data(&world, &snap));
+        snap.run_system(test_after_setup_state, ());
+        assert_eq!(*snap.resource::<Vel>(), Vel(1, 2));
+        assert_eq!(*world.resource::<Vel>(), Vel(3, 2));
+    }
+
     #[test]
     fn world_is_send() {
         send(World::new())
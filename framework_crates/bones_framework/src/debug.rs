@@ -111,3 +111,96 @@ pub fn frame_diagnostic_window(
 
     egui_ctx.set_state(window_state);
 }
+
+/// State of the system timings window. Stored in [`EguiCtx`] state,
+/// setting open = true will open window if plugin installed.
+#[derive(Clone, Default)]
+pub struct SystemTimingsWindowState {
+    /// Is window open?
+    pub open: bool,
+    /// Whether the window was open the last time it was rendered.
+    was_open: bool,
+    /// The sessions that the window enabled the timings of, which are disabled again when it is
+    /// closed.
+    enabled_timings: Vec<Ustr>,
+}
+
+/// The number of systems listed for each session in the system timings window.
+const SLOWEST_SYSTEMS_COUNT: usize = 10;
+
+/// If installed, allows opening egui window with [`SystemTimingsWindowState`] in [`EguiCtx`] state
+/// to list the slowest systems of each session.
+///
+/// The session that the window is rendered in is running while the window lists the sessions, so
+/// it isn't listed itself. Install the plugin in a session that doesn't need to be profiled, like a
+/// dedicated debug session.
+pub fn system_timings_plugin(core: &mut Session) {
+    core.stages
+        .add_system_to_stage(CoreStage::Last, system_timings_window);
+}
+
+/// Renders the system timings window in Egui if window is set to open in
+/// [`SystemTimingsWindowState`] stored in [`EguiCtx`] state.
+///
+/// While the window is open, the [`SystemStages::timings`] of every other session are enabled.
+/// When it is closed, the timings that it enabled are disabled again, while timings that were
+/// already enabled are left alone.
+pub fn system_timings_window(mut sessions: ResMut<Sessions>, egui_ctx: ResMut<EguiCtx>) {
+    let mut window_state = egui_ctx.get_state::<SystemTimingsWindowState>();
+    let SystemTimingsWindowState {
+        open: window_open,
+        was_open,
+        enabled_timings,
+    } = &mut window_state;
+
+    if *window_open {
+        egui::Window::new("System Timings")
+            .id(egui::Id::new("system_timings"))
+            .default_width(500.0)
+            .open(window_open)
+            .show(&egui_ctx, |ui| {
+                let mut names = sessions.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                names.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+                for name in names {
+                    let session = sessions.get_mut(name).unwrap();
+                    if session.stages.timings.is_none() {
+                        enabled_timings.push(name);
+                    }
+                    let timings = session.stages.timings.get_or_insert_with(default);
+
+                    ui.horizontal(|ui| {
+                        ui.heading(name.as_str());
+                        if ui.button("Reset").clicked() {
+                            timings.reset();
+                        }
+                    });
+                    ui.monospace(format!("{:>10} {:>10}  System", "Avg (ms)", "Max (ms)"));
+                    for (system, stats) in timings
+                        .slowest_systems()
+                        .into_iter()
+                        .take(SLOWEST_SYSTEMS_COUNT)
+                    {
+                        ui.monospace(format!(
+                            "{avg:10.3} {max:10.3}  {system}",
+                            avg = stats.average.as_secs_f64() * 1000.0,
+                            max = stats.max.as_secs_f64() * 1000.0,
+                        ));
+                    }
+                    ui.separator();
+                }
+            });
+    }
+
+    // Stop timing the systems that we started timing once the window is closed.
+    if *was_open && !*window_open {
+        for name in enabled_timings.drain(..) {
+            if let Some(session) = sessions.get_mut(name) {
+                session.stages.timings = None;
+            }
+        }
+    }
+    *was_open = *window_open;
+
+    egui_ctx.set_state(window_state);
+}
@@ -111,6 +111,10 @@ impl<T: HasSchema> SystemParam for Localization<'_, T> {
             world.resources.get_cell::<RootLocalizationFieldIdx>(),
         )
     }
+    fn access(access: &mut SystemAccess) {
+        access.read_resource(AssetServer::schema());
+        access.write_resource(RootLocalizationFieldIdx::schema());
+    }
     fn borrow<'s>(
         world: &'s World,
         (asset_server, field_idx): &'s mut Self::State,
@@ -18,6 +18,9 @@ impl<'a, T: HasSchema> SystemParam for Root<'a, T> {
     fn get_state(world: &World) -> Self::State {
         (*world.resources.get::<AssetServer>().unwrap()).clone()
     }
+    fn access(access: &mut SystemAccess) {
+        access.read_resource(AssetServer::schema());
+    }
     fn borrow<'s>(_world: &'s World, asset_server: &'s mut Self::State) -> Self::Param<'s> {
         Root(asset_server.root())
     }
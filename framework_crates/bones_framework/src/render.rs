@@ -27,7 +27,9 @@ pub mod ui;
 pub fn render_plugin(session: &mut Session) {
     session
         .install_plugin(sprite::sprite_plugin)
-        .install_plugin(camera::plugin);
+        .install_plugin(camera::plugin)
+        // Added after the camera plugin so that camera shake is included in the global transform.
+        .add_system_to_stage(CoreStage::Last, transform::propagate_transforms);
 
     #[cfg(feature = "ui")]
     session.install_plugin(ui::ui_plugin);
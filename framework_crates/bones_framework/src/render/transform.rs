@@ -1,11 +1,12 @@
-//! Transform component.
+//! Transform components.
 
 use crate::prelude::*;
 
 /// The main transform component.
 ///
-/// Currently we don't have a hierarchy, and this is therefore a global transform.
-#[derive(Clone, Copy, Debug, HasSchema)]
+/// If the entity has a [`Parent`], this is relative to the parent's transform, otherwise it is
+/// relative to the world. See [`GlobalTransform`] for the resulting world transform.
+#[derive(Clone, Copy, Debug, PartialEq, HasSchema)]
 #[repr(C)]
 pub struct Transform {
     /// The position of the entity in the world.
@@ -47,4 +48,84 @@ impl Transform {
     pub fn from_scale(scale: Vec3) -> Self {
         Self { scale, ..default() }
     }
+
+    /// Combine this transform with a `child` transform that is relative to it, returning the
+    /// child transform relative to the parent of this transform.
+    pub fn mul_transform(&self, child: &Transform) -> Transform {
+        Transform {
+            translation: self.translation + self.rotation * (self.scale * child.translation),
+            rotation: self.rotation * child.rotation,
+            scale: self.scale * child.scale,
+        }
+    }
+}
+
+/// The transform of an entity relative to the world, taking its ancestors' [`Transform`]s into
+/// account.
+///
+/// This is updated by [`propagate_transforms()`] at the end of every frame, and shouldn't be
+/// modified directly. Renderers should draw entities at this transform, falling back to the
+/// [`Transform`] of entities that haven't been propagated yet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, HasSchema, Deref, DerefMut)]
+#[repr(C)]
+pub struct GlobalTransform(pub Transform);
+
+/// System that updates the [`GlobalTransform`] of every entity with a [`Transform`].
+///
+/// Entities are visited in entity order starting from the roots of the hierarchy, and children are
+/// visited in the order they were added, so the result is deterministic. Global transforms are
+/// only written when they change, so they aren't reported as changed every frame.
+///
+/// Entities without a [`Transform`] count as an identity transform for their children, and their
+/// [`GlobalTransform`] is removed, if they have one.
+pub fn propagate_transforms(
+    entities: Res<Entities>,
+    transforms: Comp<Transform>,
+    parents: Comp<Parent>,
+    children: Comp<Children>,
+    mut global_transforms: CompMut<GlobalTransform>,
+) {
+    fn propagate(
+        entity: Entity,
+        parent: &Transform,
+        transforms: &ComponentStore<Transform>,
+        children: &ComponentStore<Children>,
+        global_transforms: &mut ComponentStore<GlobalTransform>,
+    ) {
+        let global = match transforms.get(entity) {
+            Some(transform) => {
+                let global = parent.mul_transform(transform);
+                if global_transforms.get(entity).map(|x| x.0) != Some(global) {
+                    global_transforms.insert(entity, GlobalTransform(global));
+                }
+                global
+            }
+            None => *parent,
+        };
+
+        if let Some(entity_children) = children.get(entity) {
+            for &child in entity_children.iter() {
+                propagate(child, &global, transforms, children, global_transforms);
+            }
+        }
+    }
+
+    // Remove the global transforms that are left over from removed transforms.
+    let stale = entities
+        .iter_with((&global_transforms, Without(&transforms)))
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>();
+    for entity in stale {
+        global_transforms.remove(entity);
+    }
+
+    for (root, ()) in entities.iter_with(Without(&parents)) {
+        propagate(
+            root,
+            &Transform::default(),
+            &transforms,
+            &children,
+            &mut global_transforms,
+        );
+    }
 }
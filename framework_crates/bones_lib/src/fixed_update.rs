@@ -0,0 +1,204 @@
+//! Fixed timestep stage.
+//!
+//! The [`FixedUpdateStage`] runs its systems zero or more times per frame, so that they always run
+//! at the same rate, no matter the frame rate. It works like the accumulator of the networked
+//! session runner: the [`Time`] that has passed since the last frame is added to an accumulator,
+//! and the systems are run once for every full timestep in it.
+//!
+//! The stage isn't part of the core stages, it must be added to the [`SystemStages`] of a session:
+//!
+//! ```
+//! # use bones_lib::prelude::*;
+//! # fn physics() {}
+//! let mut stages = SystemStages::with_core_stages();
+//! stages
+//!     .insert_stage_before(CoreStage::Update, FixedUpdateStage::new(1.0 / 60.0))
+//!     .add_system_to_stage(FixedUpdate, physics);
+//! ```
+
+use crate::prelude::*;
+
+use instant::Duration;
+
+/// The [`StageLabel`] of the [`FixedUpdateStage`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FixedUpdate;
+
+impl StageLabel for FixedUpdate {
+    fn name(&self) -> String {
+        "FixedUpdate".into()
+    }
+
+    fn id(&self) -> Ulid {
+        Ulid(2021715443712049227861548102362740511)
+    }
+}
+
+/// Resource updated by the [`FixedUpdateStage`] every frame.
+///
+/// It also holds the state of the stage, so that it is saved and restored along with world
+/// snapshots, and the stage runs the same number of steps when a frame is run again after a
+/// rollback.
+#[derive(HasSchema, Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct FixedTimestep {
+    /// The fixed timestep, in seconds.
+    pub step: f64,
+    /// The number of times that the systems of the stage were run this frame.
+    pub steps: u32,
+    /// How far the current frame is between the last fixed update and the next one, from `0.0`
+    /// to `1.0`.
+    ///
+    /// This can be used to interpolate between the previous and the current state of things that
+    /// are updated in the fixed update stage when rendering them.
+    pub alpha: f64,
+    /// The time that has not been simulated yet, in seconds.
+    pub accumulator: f64,
+    /// The clock that the systems of the stage see, created from the frame [`Time`] on the first
+    /// run.
+    pub time: Time,
+}
+
+/// A stage that runs its systems at a fixed rate.
+///
+/// While the systems run, the [`Time`] resource is replaced with a clock that advances by exactly
+/// one timestep every time they are run, so they don't need to know that they are running in a
+/// fixed update stage. The [`FixedTimestep`] resource is updated after the systems have run, and
+/// keeps the time that has not been simulated yet along with that clock.
+///
+/// The stage does nothing if there is no [`Time`] resource in the world.
+pub struct FixedUpdateStage {
+    /// The fixed timestep, in seconds.
+    pub step: f64,
+    /// The systems and run conditions of the stage, which are run once per timestep.
+    pub stage: SimpleSystemStage,
+}
+
+impl FixedUpdateStage {
+    /// Create a new, empty stage with the given timestep, in seconds.
+    pub fn new(step: f64) -> Self {
+        Self {
+            step,
+            stage: SimpleSystemStage::new(FixedUpdate),
+        }
+    }
+}
+
+impl SystemStage for FixedUpdateStage {
+    fn id(&self) -> Ulid {
+        self.stage.id
+    }
+
+    fn name(&self) -> String {
+        self.stage.name.clone()
+    }
+
+    fn run(&mut self, world: &World) -> bool {
+        let Some(frame_time) = world.resources.get::<Time>().map(|time| *time) else {
+            return false;
+        };
+        let mut fixed = world
+            .resources
+            .get::<FixedTimestep>()
+            .map(|fixed| *fixed)
+            .unwrap_or(FixedTimestep {
+                time: frame_time,
+                ..default()
+            });
+        fixed.accumulator += frame_time.delta_seconds_f64();
+
+        let mut steps = 0;
+        let mut ran = false;
+        while fixed.accumulator >= self.step {
+            fixed.accumulator -= self.step;
+            steps += 1;
+
+            fixed.time.advance_exact(Duration::from_secs_f64(self.step));
+            world.resources.insert(fixed.time);
+            ran |= self.stage.run(world);
+        }
+        world.resources.insert(frame_time);
+
+        world.resources.insert(FixedTimestep {
+            step: self.step,
+            steps,
+            alpha: fixed.accumulator / self.step,
+            ..fixed
+        });
+        ran
+    }
+
+    fn systems(&self) -> &[StaticSystem<(), ()>] {
+        self.stage.systems()
+    }
+
+    fn run_conditions(&self) -> &[StaticSystem<(), bool>] {
+        self.stage.run_conditions()
+    }
+
+    fn add_system(&mut self, system: StaticSystem<(), ()>) {
+        self.stage.add_system(system);
+    }
+
+    fn add_run_condition(&mut self, condition: StaticSystem<(), bool>) {
+        self.stage.add_run_condition(condition);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use crate::prelude::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn fixed_update_steps() {
+        let mut world = World::new();
+        let mut time = Time::default();
+        time.update_with_instant(Instant::now());
+        world.insert_resource(time);
+
+        let mut stages = SystemStages::with_core_stages();
+        stages
+            .insert_stage_before(CoreStage::Update, FixedUpdateStage::new(0.25))
+            .add_system_to_stage(FixedUpdate, |time: Res<Time>, mut runs: ResMutInit<u32>| {
+                assert_eq!(time.delta_seconds_f64(), 0.25);
+                *runs += 1;
+            });
+
+        let mut run_frame = |world: &mut World, delta: f64| {
+            world
+                .resource_mut::<Time>()
+                .advance_exact(Duration::from_secs_f64(delta));
+            stages.run(world);
+            *world.resource::<FixedTimestep>()
+        };
+
+        let fixed = run_frame(&mut world, 0.625);
+        assert_eq!((fixed.steps, fixed.alpha), (2, 0.5));
+        let fixed = run_frame(&mut world, 0.125);
+        assert_eq!((fixed.steps, fixed.alpha), (1, 0.0));
+        let fixed = run_frame(&mut world, 0.125);
+        assert_eq!((fixed.steps, fixed.alpha), (0, 0.5));
+        assert_eq!(*world.resource::<u32>(), 3);
+
+        // The frame time is restored after the fixed update.
+        assert_eq!(world.resource::<Time>().delta_seconds_f64(), 0.125);
+
+        // The accumulator and the fixed clock are restored with snapshots, so running the same
+        // frames again after a rollback gives the same steps.
+        let snapshot = world.clone();
+        let frames = [0.375, 0.125, 0.25];
+        let steps = frames.map(|delta| run_frame(&mut world, delta).steps);
+        assert_eq!(steps, [2, 0, 1]);
+        let fixed_time = world.resource::<FixedTimestep>().time.elapsed();
+        world = snapshot;
+        assert_eq!(
+            frames.map(|delta| run_frame(&mut world, delta).steps),
+            steps
+        );
+        assert_eq!(world.resource::<FixedTimestep>().time.elapsed(), fixed_time);
+        assert_eq!(*world.resource::<u32>(), 6);
+    }
+}
@@ -11,12 +11,13 @@ pub use bones_ecs as ecs;
 /// Bones lib prelude
 pub mod prelude {
     pub use crate::{
-        ecs::prelude::*, instant::Instant, time::*, Game, GamePlugin, Session, SessionCommand,
-        SessionOptions, SessionPlugin, SessionRunner, Sessions,
+        ecs::prelude::*, fixed_update::*, instant::Instant, time::*, Game, GamePlugin, Session,
+        SessionCommand, SessionOptions, SessionPlugin, SessionRunner, Sessions,
     };
 }
 
 pub use instant;
+pub mod fixed_update;
 pub mod time;
 
 use std::{collections::VecDeque, fmt::Debug, sync::Arc};
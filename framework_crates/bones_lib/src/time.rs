@@ -218,9 +218,23 @@ impl Time {
 #[allow(clippy::float_cmp)]
 mod tests {
     use super::Time;
+    use crate::prelude::*;
 
     use std::time::{Duration, Instant};
 
+    #[test]
+    fn snapshot_diff() {
+        let mut world = World::new();
+        world.insert_resource(Time::default());
+        world.init_resource::<CommandQueue>();
+        world.resource_mut::<Time>().update();
+
+        // The instants of the clock are opaque, but they don't make identical worlds differ.
+        let diff = world.clone().diff(&world);
+        assert!(diff.is_empty(), "{diff}");
+        assert!(!diff.uncomparable.is_empty());
+    }
+
     #[test]
     fn update_test() {
         let start_instant = Instant::now();
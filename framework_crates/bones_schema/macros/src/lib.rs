@@ -298,10 +298,17 @@ pub fn derive_has_schema(input: TokenStream) -> TokenStream {
         }
     })();
 
+    // Generic types get their full name from the type name, so that it is different for every set
+    // of type parameters.
+    let full_name = if input.generic_params().is_some() {
+        quote!(::std::any::type_name::<Self>())
+    } else {
+        quote!(concat!(module_path!(), "::", stringify!(#name)))
+    };
     let schema_register = quote! {
         #schema_mod::registry::SCHEMA_REGISTRY.register(#schema_mod::SchemaData {
             name: stringify!(#name).into(),
-            full_name: concat!(module_path!(), "::", stringify!(#name)).into(),
+            full_name: #full_name.into(),
             type_id: Some(::std::any::TypeId::of::<Self>()),
             kind: #schema_kind,
             type_data: #type_datas,
@@ -459,11 +459,14 @@ impl PartialEq for SchemaVec {
         let Some(eq_fn) = &self.schema.eq_fn else {
             panic!("Schema doesn't have an eq_fn");
         };
+        if self.len != other.len {
+            return false;
+        }
 
         for i in 0..self.len {
             unsafe {
                 let a = self.buffer.unchecked_idx(i);
-                let b = self.buffer.unchecked_idx(i);
+                let b = other.buffer.unchecked_idx(i);
                 if !(eq_fn.get())(a, b) {
                     return false;
                 }
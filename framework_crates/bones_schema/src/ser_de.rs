@@ -128,7 +128,10 @@ mod serializer_deserializer {
                     PrimitiveRef::String(n) => serializer.serialize_str(n),
                     PrimitiveRef::Opaque { .. } => {
                         use serde::ser::Error;
-                        Err(S::Error::custom("Cannot serialize opaque types"))
+                        Err(S::Error::custom(format!(
+                            "Cannot serialize opaque type `{}`",
+                            self.0.schema().full_name
+                        )))
                     }
                 },
             };
@@ -406,6 +409,26 @@ mod serializer_deserializer {
             var_access.newtype_variant_seed(value_ptr)?;
             Ok(())
         }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            // Formats such as JSON or MessagePack store enum variants with fields as a map with a
+            // single entry, from the variant name to the variant value.
+            let Some(value_ptr) = map.next_key_seed(EnumLoad(self.0))? else {
+                return Err(A::Error::custom(
+                    "Expected a map with one entry for the enum variant",
+                ));
+            };
+            map.next_value_seed(value_ptr)?;
+            if map.next_key::<serde::de::IgnoredAny>()?.is_some() {
+                return Err(A::Error::custom(
+                    "Expected a map with one entry for the enum variant",
+                ));
+            }
+            Ok(())
+        }
     }
 
     struct EnumLoad<'a>(SchemaRefMut<'a>);
@@ -327,4 +327,8 @@ fn generic_has_schema_impls_dont_match() {
     assert_ne!(SVec::<u32>::schema(), SVec::<u8>::schema());
     assert_ne!(SBox::<u32>::schema(), SBox::<u8>::schema());
     assert_ne!(HasGeneric::<u32>::schema(), HasGeneric::<u64>::schema());
+    assert_ne!(
+        HasGeneric::<u32>::schema().full_name,
+        HasGeneric::<u64>::schema().full_name
+    );
 }
@@ -46,9 +46,18 @@ pub struct LuaPluginLoaderSessionPlugin(pub Arc<Vec<Handle<LuaPlugin>>>);
 #[derive(HasSchema, Deref, DerefMut, Default, Clone)]
 pub struct LuaPlugins(pub Arc<Vec<Handle<LuaPlugin>>>);
 
+/// Resource containing the component hooks that have been added to the world for the lua plugins,
+/// keyed by plugin, hook kind and component.
+///
+/// Each of these world hooks runs all of the plugin's current hooks with that kind and component,
+/// so the hooks aren't added again when the plugin is reloaded.
+#[derive(HasSchema, Deref, DerefMut, Default, Clone)]
+pub struct LuaComponentHooks(pub HashSet<(Handle<LuaPlugin>, ComponentHookKind, SchemaId)>);
+
 impl SessionPlugin for LuaPluginLoaderSessionPlugin {
     fn install(self, session: &mut Session) {
         session.world.insert_resource(LuaPlugins(self.0));
+        session.world.init_resource::<LuaComponentHooks>();
 
         for lua_stage in [
             CoreStage::First,
@@ -87,6 +96,31 @@ impl SessionPlugin for LuaPluginLoaderSessionPlugin {
                                 let mut systems = plugin.systems.borrow_mut();
                                 let systems = systems.as_loaded_mut();
 
+                                for &(kind, schema, _) in &systems.component_hooks {
+                                    let plugin_handle = *plugin_handle;
+                                    world.with(|world| {
+                                        let key = (plugin_handle, kind, schema.id());
+                                        if !world.resource_mut::<LuaComponentHooks>().insert(key) {
+                                            return;
+                                        }
+                                        let hook =
+                                            move |commands: &mut Commands, entity: Entity| {
+                                                commands.add(
+                                                    move |engine: Res<LuaEngine>, world: &World| {
+                                                        engine.run_component_hooks(
+                                                            world,
+                                                            plugin_handle,
+                                                            kind,
+                                                            schema,
+                                                            entity,
+                                                        )
+                                                    },
+                                                );
+                                            };
+                                        world.add_component_hook(schema, kind, Arc::new(hook));
+                                    });
+                                }
+
                                 for (has_run, closure) in &mut systems.startup {
                                     if !*has_run {
                                         let executor = lua.enter(|ctx| {
@@ -160,6 +194,7 @@ impl WorldRef {
             ("components", bindings::components::metatable),
             ("resources", bindings::resources::metatable),
             ("assets", bindings::assets::metatable),
+            ("commands", bindings::commands::metatable),
         ] {
             let data = UserData::new_static(&ctx, self.clone());
             data.set_metatable(&ctx, Some(ctx.singletons().get(ctx, metatable)));
@@ -311,6 +346,58 @@ impl LuaEngine {
         });
     }
 
+    /// Run the component hooks of the given kind and component that were registered by a lua
+    /// plugin, with the given entity.
+    fn run_component_hooks(
+        &self,
+        world: &World,
+        plugin: Handle<LuaPlugin>,
+        kind: ComponentHookKind,
+        schema: &'static Schema,
+        entity: Entity,
+    ) {
+        self.exec(|lua| {
+            let asset_server = world.resource::<AssetServer>();
+            let Some(plugin) = asset_server.try_get(plugin) else {
+                return;
+            };
+            let plugin = plugin.unwrap();
+            // The plugin may be in the middle of being reloaded.
+            if !plugin.has_loaded() {
+                return;
+            }
+            let systems = plugin.systems.borrow();
+            let hooks = systems
+                .as_loaded()
+                .component_hooks
+                .iter()
+                .filter(|(k, s, _)| *k == kind && s.id() == schema.id());
+
+            Frozen::<Freeze![&'freeze World]>::in_scope(world, |world| {
+                for (.., closure) in hooks {
+                    let executor = lua.enter(|ctx| {
+                        let env = ctx.singletons().get(ctx, bindings::env);
+                        WorldRef(world).add_to_env(ctx, env);
+
+                        let closure = ctx.registry().fetch(closure);
+                        let entity = bindings::EcsRef {
+                            data: bindings::EcsRefData::Free(Rc::new(AtomicCell::new(
+                                SchemaBox::new(entity),
+                            ))),
+                            path: default(),
+                        }
+                        .into_value(ctx);
+                        let ex = Executor::start(ctx, closure.into(), entity);
+                        ctx.registry().stash(&ctx, ex)
+                    });
+                    if let Err(e) = lua.execute::<()>(&executor) {
+                        tracing::error!("Error running lua component hook: {e}");
+                    }
+                }
+            });
+        });
+    }
+
     /// Run a lua script as a system on the given world.
     pub fn run_script_system(&self, world: &World, script: Handle<LuaScript>) {
         self.exec(|lua| {
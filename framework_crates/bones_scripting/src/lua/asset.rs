@@ -120,7 +120,7 @@ fn session_metatable(ctx: Context) -> Table {
             "__tostring",
             Callback::from_fn(&ctx, |ctx, _fuel, mut stack| {
                 stack.push_front(
-                    piccolo::String::from_static(&ctx, "Session { add_system_to_stage }").into(),
+                    piccolo::String::from_static(&ctx, "Session { add_startup_system, add_system_to_stage, on_insert, on_replace, on_remove }").into(),
                 );
                 Ok(CallbackReturn::Return)
             }),
@@ -149,6 +149,28 @@ fn session_metatable(ctx: Context) -> Table {
             Ok(CallbackReturn::Return)
         }),
     );
+    let add_component_hook_callback = |kind: ComponentHookKind| {
+        ctx.registry().stash(
+            &ctx,
+            Callback::from_fn(&ctx, move |ctx, _fuel, mut stack| {
+                let (this, schema, closure): (UserData, UserData, Closure) = stack.consume(ctx)?;
+                let this = this.downcast_static::<LuaPluginSystemsCell>()?;
+                let schema = *schema.downcast_static::<&Schema>()?;
+
+                let mut systems = this.borrow_mut();
+                systems.as_loaded_mut().component_hooks.push((
+                    kind,
+                    schema,
+                    ctx.registry().stash(&ctx, closure),
+                ));
+
+                Ok(CallbackReturn::Return)
+            }),
+        )
+    };
+    let on_insert_callback = add_component_hook_callback(ComponentHookKind::Insert);
+    let on_replace_callback = add_component_hook_callback(ComponentHookKind::Replace);
+    let on_remove_callback = add_component_hook_callback(ComponentHookKind::Remove);
     let add_system_to_stage_callback = ctx.registry().stash(
         &ctx,
         Callback::from_fn(&ctx, move |ctx, _fuel, mut stack| {
@@ -182,6 +204,15 @@ fn session_metatable(ctx: Context) -> Table {
                     b"add_startup_system" => {
                         stack.push_front(ctx.registry().fetch(&add_startup_system_callback).into());
                     }
+                    b"on_insert" => {
+                        stack.push_front(ctx.registry().fetch(&on_insert_callback).into());
+                    }
+                    b"on_replace" => {
+                        stack.push_front(ctx.registry().fetch(&on_replace_callback).into());
+                    }
+                    b"on_remove" => {
+                        stack.push_front(ctx.registry().fetch(&on_remove_callback).into());
+                    }
                     _ => (),
                 }
 
@@ -241,6 +272,11 @@ pub struct LuaPluginSystems {
     pub startup: Vec<(bool, StashedClosure)>,
     /// Systems that run in the core stages.
     pub core_stages: Vec<(CoreStage, StashedClosure)>,
+    /// Component hooks, called with the entity whose component changed.
+    ///
+    /// They are run by a hook added to the world for each plugin, kind and component, see
+    /// [`LuaComponentHooks`][crate::lua::LuaComponentHooks].
+    pub component_hooks: Vec<(ComponentHookKind, &'static Schema, StashedClosure)>,
 }
 
 struct LuaPluginLoader;
@@ -8,6 +8,7 @@ use piccolo::{
 use super::*;
 
 pub mod assets;
+pub mod commands;
 pub mod components;
 pub mod entities;
 pub mod resources;
@@ -0,0 +1,132 @@
+use super::*;
+
+pub fn metatable(ctx: Context) -> Table {
+    let metatable = Table::new(&ctx);
+    metatable
+        .set(
+            ctx,
+            "__tostring",
+            Callback::from_fn(&ctx, |ctx, _fuel, mut stack| {
+                stack.push_front(
+                    piccolo::String::from_static(&ctx, "Commands { spawn, insert, remove, kill }")
+                        .into(),
+                );
+                Ok(CallbackReturn::Return)
+            }),
+        )
+        .unwrap();
+    metatable
+        .set(ctx, "__newindex", ctx.singletons().get(ctx, no_newindex))
+        .unwrap();
+
+    let spawn_callback = ctx.registry().stash(
+        &ctx,
+        Callback::from_fn(&ctx, |ctx, _fuel, mut stack| {
+            let world: &WorldRef = stack.consume(ctx)?;
+
+            let entity = world.with(|world| {
+                let entity = Commands::new(world).spawn().id();
+                Ok::<_, anyhow::Error>(entity)
+            })?;
+
+            let ecsref = EcsRef {
+                data: EcsRefData::Free(Rc::new(AtomicCell::new(SchemaBox::new(entity)))),
+                path: default(),
+            }
+            .into_value(ctx);
+            stack.push_front(ecsref);
+
+            Ok(CallbackReturn::Return)
+        }),
+    );
+    let insert_callback = ctx.registry().stash(
+        &ctx,
+        Callback::from_fn(&ctx, |ctx, _fuel, mut stack| {
+            let (world, entity_ecsref, value_ecsref): (&WorldRef, &EcsRef, &EcsRef) =
+                stack.consume(ctx)?;
+
+            let b = entity_ecsref.borrow();
+            let entity = *b.schema_ref()?.try_cast::<Entity>()?;
+
+            let value = {
+                let b = value_ecsref.borrow();
+                let value = b.schema_ref()?;
+                value.clone_into_box()
+            };
+
+            world.with(|world| {
+                Commands::new(world).entity(entity).insert_box(value);
+                Ok::<_, anyhow::Error>(())
+            })?;
+
+            Ok(CallbackReturn::Return)
+        }),
+    );
+    let remove_callback = ctx.registry().stash(
+        &ctx,
+        Callback::from_fn(&ctx, |ctx, _fuel, mut stack| {
+            let (world, entity_ecsref, schema): (&WorldRef, &EcsRef, UserData) =
+                stack.consume(ctx)?;
+
+            let b = entity_ecsref.borrow();
+            let entity = *b.schema_ref()?.try_cast::<Entity>()?;
+
+            let schema = *schema.downcast_static::<&Schema>()?;
+
+            world.with(|world| {
+                Commands::new(world).entity(entity).remove_by_schema(schema);
+                Ok::<_, anyhow::Error>(())
+            })?;
+
+            Ok(CallbackReturn::Return)
+        }),
+    );
+    let kill_callback = ctx.registry().stash(
+        &ctx,
+        Callback::from_fn(&ctx, |ctx, _fuel, mut stack| {
+            let (world, entity_ecsref): (&WorldRef, &EcsRef) = stack.consume(ctx)?;
+
+            let b = entity_ecsref.borrow();
+            let entity = *b.schema_ref()?.try_cast::<Entity>()?;
+
+            world.with(|world| {
+                Commands::new(world).kill(entity);
+                Ok::<_, anyhow::Error>(())
+            })?;
+
+            Ok(CallbackReturn::Return)
+        }),
+    );
+
+    metatable
+        .set(
+            ctx,
+            "__index",
+            Callback::from_fn(&ctx, move |ctx, _fuel, mut stack| {
+                let (_world, key): (&WorldRef, lua::Value) = stack.consume(ctx)?;
+
+                if let Value::String(key) = key {
+                    match key.as_bytes() {
+                        b"spawn" => {
+                            stack.push_front(ctx.registry().fetch(&spawn_callback).into());
+                        }
+                        b"insert" => {
+                            stack.push_front(ctx.registry().fetch(&insert_callback).into());
+                        }
+                        b"remove" => {
+                            stack.push_front(ctx.registry().fetch(&remove_callback).into());
+                        }
+                        b"kill" => {
+                            stack.push_front(ctx.registry().fetch(&kill_callback).into());
+                        }
+                        _ => (),
+                    }
+                }
+
+                Ok(CallbackReturn::Return)
+            }),
+        )
+        .unwrap();
+
+    metatable
+}
@@ -8,8 +8,11 @@ pub fn metatable(ctx: Context) -> Table {
             "__tostring",
             Callback::from_fn(&ctx, |ctx, _fuel, mut stack| {
                 stack.push_front(
-                    piccolo::String::from_static(&ctx, "World { resources, components, assets }")
-                        .into(),
+                    piccolo::String::from_static(
+                        &ctx,
+                        "World { resources, components, assets, commands }",
+                    )
+                    .into(),
                 );
                 Ok(CallbackReturn::Return)
             }),
@@ -30,6 +33,7 @@ pub fn metatable(ctx: Context) -> Table {
                 let resources_metatable = singletons.get(ctx, super::resources::metatable);
                 let components_metatable = singletons.get(ctx, super::components::metatable);
                 let assets_metatable = singletons.get(ctx, super::assets::metatable);
+                let commands_metatable = singletons.get(ctx, super::commands::metatable);
 
                 match key.as_bytes() {
                     b"resources" => {
@@ -47,6 +51,11 @@ pub fn metatable(ctx: Context) -> Table {
                         assets.set_metatable(&ctx, Some(assets_metatable));
                         stack.push_front(assets.into());
                     }
+                    b"commands" => {
+                        let commands = UserData::new_static(&ctx, world.clone());
+                        commands.set_metatable(&ctx, Some(commands_metatable));
+                        stack.push_front(commands.into());
+                    }
                     _ => (),
                 }
 